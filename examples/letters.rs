@@ -6,8 +6,12 @@ use winit::{
     event_loop::ControlFlow,
 };
 use bui::{
+    blend,
+    color,
     rect,
     renderer,
+    resolution_buffer,
+    stroke,
     line,
     ttf,
     ttf_outline,
@@ -41,10 +45,9 @@ constrainer::create_constrainer!(Contrainer {
                 resy: resy,
                 aspect: 1.0,
             }.into(),
-            r: 1.0,
-            g: 1.0,
-            b: 1.0,
-            a: 1.0
+            fill: color::Color::WHITE.into(),
+            corner_radii: rect::CornerRadii::ZERO,
+            stroke: stroke::Stroke::NONE,
         };
     
         let rectangle = rect::RectDescriptor {
@@ -56,10 +59,9 @@ constrainer::create_constrainer!(Contrainer {
                 resy: resy,
                 aspect: 2.0
             }.into(),
-            r: 1.0,
-            g: 0.0,
-            b: 0.0,
-            a: 1.0
+            fill: color::Color::from_hex("#ff0000").unwrap().into(),
+            corner_radii: rect::CornerRadii::ZERO,
+            stroke: stroke::Stroke::NONE,
         };
 
         let lines = ttf_outline::FillTextFromFaceCurvesAsLines {
@@ -71,11 +73,11 @@ constrainer::create_constrainer!(Contrainer {
             resy,
         };
 
-        rect_renderer.set_rect_buffer(renderer.queue(), &[
+        rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
             square.into(),
             rectangle.into()
         ]);
-        line_renderer.set_line_buffer(renderer.queue(), Vec::from(lines).as_slice());
+        line_renderer.set_line_buffer(renderer.device(), renderer.queue(), Vec::from(lines).as_slice());
     }
 
     opgenset (resx, resy)
@@ -91,9 +93,11 @@ fn main() {
             height: 360,
         })
         .build(&event_loop).unwrap();
-    let mut renderer = futures::executor::block_on(renderer::Renderer::new(&window));
-    let mut rect_renderer = rect::RectRenderer::new(renderer.device(), renderer.config().format, 2);
-    let mut line_renderer = line::LineRenderer::new(renderer.device(), renderer.config().format, 12800);
+    let mut renderer = futures::executor::block_on(renderer::Renderer::new(&window)).unwrap();
+    let resolution_buffer = resolution_buffer::ResolutionBuffer::new(renderer.device());
+    resolution_buffer.set(&[window.inner_size().width as f32, window.inner_size().height as f32], renderer.queue());
+    let mut rect_renderer = rect::RectRenderer::new(renderer.device(), renderer.config().format, renderer.sample_count(), blend::BlendMode::Alpha, &resolution_buffer);
+    let mut line_renderer = line::LineRenderer::new(renderer.device(), renderer.config().format, renderer.sample_count(), blend::BlendMode::Alpha);
 
     let font_bytes = include_bytes!("NotoSansJP-Regular.otf");
     let font_face = owned_ttf_parser::OwnedFace::from_vec(font_bytes.to_vec(), 0).unwrap();
@@ -118,6 +122,7 @@ fn main() {
                 match event {
                     WindowEvent::Resized(physical_size) => {
                         renderer.resize(*physical_size);
+                        resolution_buffer.set(&[physical_size.width as f32, physical_size.height as f32], renderer.queue());
                         constrainer.set_resx_resy(
                             physical_size.width as f32,
                             physical_size.height as f32,
@@ -132,6 +137,7 @@ fn main() {
                         ..
                     } => {
                         renderer.resize(**new_inner_size);
+                        resolution_buffer.set(&[new_inner_size.width as f32, new_inner_size.height as f32], renderer.queue());
                         constrainer.set_resx_resy(
                             new_inner_size.width as f32,
                             new_inner_size.height as f32,
@@ -145,22 +151,22 @@ fn main() {
                 }
             }
             Event::RedrawRequested(_) => {
-                match renderer.surface().get_current_texture() {
+                match renderer.get_current_texture() {
                     Ok(surface_texture) => {
                         let view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
                         let mut encoder = renderer.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
                             label: Some("Render encoder"),
                         });
-                        rect_renderer.render_all(&mut encoder, &view, wgpu::LoadOp::Clear(wgpu::Color::BLACK));
-                        line_renderer.render_all(&mut encoder, &view, wgpu::LoadOp::Load);
+                        rect_renderer.render_all(&mut encoder, renderer.render_target(&view), wgpu::LoadOp::Clear(wgpu::Color::BLACK));
+                        line_renderer.render_all(&mut encoder, renderer.render_target(&view), wgpu::LoadOp::Load);
                         renderer.queue().submit(std::iter::once(encoder.finish()));
                         surface_texture.present();
                     },
-                    Err(wgpu::SurfaceError::Lost) => {
+                    Err(bui::Error::Surface(wgpu::SurfaceError::Lost)) => {
                         eprintln!("Surface lost!");
                         renderer.reconfigure();
                     },
-                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                    Err(bui::Error::Surface(wgpu::SurfaceError::OutOfMemory)) => {
                         eprintln!("Out of memory!");
                         *control_flow = ControlFlow::Exit;
                     },
@@ -6,8 +6,12 @@ use winit::{
     event_loop::ControlFlow,
 };
 use bui::{
+    blend,
+    color,
     rect,
-    renderer
+    renderer,
+    resolution_buffer,
+    stroke,
 };
 
 type RectRendererRef<'a> = &'a mut rect::RectRenderer;
@@ -33,10 +37,9 @@ constrainer::create_constrainer!(Contrainer {
                 resy: resy,
                 aspect: 1.0,
             }.into(),
-            r: 1.0,
-            g: 1.0,
-            b: 1.0,
-            a: 1.0
+            fill: color::Color::WHITE.into(),
+            corner_radii: rect::CornerRadii::all(24.0),
+            stroke: stroke::Stroke::NONE,
         };
     
         let rectangle = rect::RectDescriptor {
@@ -48,13 +51,12 @@ constrainer::create_constrainer!(Contrainer {
                 resy: resy,
                 aspect: 2.0
             }.into(),
-            r: 1.0,
-            g: 0.0,
-            b: 0.0,
-            a: 1.0
+            fill: color::Color::from_hex("#ff0000").unwrap().into(),
+            corner_radii: rect::CornerRadii::ZERO,
+            stroke: stroke::Stroke::NONE,
         };
     
-        rect_renderer.set_rect_buffer(renderer.queue(), &[
+        rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
             square.into(),
             rectangle.into()
         ]);
@@ -72,8 +74,10 @@ fn main() {
             height: 360,
         })
         .build(&event_loop).unwrap();
-    let mut renderer = futures::executor::block_on(renderer::Renderer::new(&window));
-    let mut rect_renderer = rect::RectRenderer::new(renderer.device(), renderer.config().format, 2);
+    let mut renderer = futures::executor::block_on(renderer::Renderer::new(&window)).unwrap();
+    let resolution_buffer = resolution_buffer::ResolutionBuffer::new(renderer.device());
+    resolution_buffer.set(&[window.inner_size().width as f32, window.inner_size().height as f32], renderer.queue());
+    let mut rect_renderer = rect::RectRenderer::new(renderer.device(), renderer.config().format, renderer.sample_count(), blend::BlendMode::Alpha, &resolution_buffer);
 
     let mut constrainer = Contrainer::new(window.inner_size().width as f32, window.inner_size().height as f32, &mut rect_renderer, &renderer);
     
@@ -94,6 +98,7 @@ fn main() {
                 match event {
                     WindowEvent::Resized(physical_size) => {
                         renderer.resize(*physical_size);
+                        resolution_buffer.set(&[physical_size.width as f32, physical_size.height as f32], renderer.queue());
                         constrainer.set_resx_resy(
                             physical_size.width as f32,
                             physical_size.height as f32,
@@ -106,6 +111,7 @@ fn main() {
                         ..
                     } => {
                         renderer.resize(**new_inner_size);
+                        resolution_buffer.set(&[new_inner_size.width as f32, new_inner_size.height as f32], renderer.queue());
                         constrainer.set_resx_resy(
                             new_inner_size.width as f32,
                             new_inner_size.height as f32,
@@ -117,21 +123,21 @@ fn main() {
                 }
             }
             Event::RedrawRequested(_) => {
-                match renderer.surface().get_current_texture() {
+                match renderer.get_current_texture() {
                     Ok(surface_texture) => {
                         let view = surface_texture.texture.create_view(&wgpu::TextureViewDescriptor::default());
                         let mut encoder = renderer.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
                             label: Some("Render encoder"),
                         });
-                        rect_renderer.render_all(&mut encoder, &view, wgpu::LoadOp::Clear(wgpu::Color::BLACK));
+                        rect_renderer.render_all(&mut encoder, renderer.render_target(&view), wgpu::LoadOp::Clear(wgpu::Color::BLACK));
                         renderer.queue().submit(std::iter::once(encoder.finish()));
                         surface_texture.present();
                     },
-                    Err(wgpu::SurfaceError::Lost) => {
+                    Err(bui::Error::Surface(wgpu::SurfaceError::Lost)) => {
                         eprintln!("Surface lost!");
                         renderer.reconfigure();
                     },
-                    Err(wgpu::SurfaceError::OutOfMemory) => {
+                    Err(bui::Error::Surface(wgpu::SurfaceError::OutOfMemory)) => {
                         eprintln!("Out of memory!");
                         *control_flow = ControlFlow::Exit;
                     },
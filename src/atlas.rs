@@ -0,0 +1,171 @@
+use crate::{
+    image::{ImageTexture, UvRect},
+    readback::RgbaImage,
+};
+
+/// Space around every image, filled with copies of its edge texels so linear filtering at its edges blends in neither
+/// its neighbours nor empty space.
+const PADDING: u32 = 1;
+
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// Where the next image on the shelf goes.
+    x: u32,
+}
+
+/// Packs rectangles into rows ("shelves") from the top of a page down. Each image goes on the shelf wasting the least
+/// height, or a new shelf when none fits.
+struct ShelfAllocator {
+    width: u32,
+    height: u32,
+    shelves: Vec<Shelf>,
+}
+
+impl ShelfAllocator {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            shelves: Vec::new(),
+        }
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<[u32; 2]> {
+        let best = self.shelves.iter_mut()
+            .filter(|shelf| shelf.height >= height && self.width-shelf.x >= width)
+            .min_by_key(|shelf| shelf.height-height);
+        if let Some(shelf) = best {
+            let position = [shelf.x, shelf.y];
+            shelf.x += width;
+            return Some(position);
+        }
+
+        let y = self.shelves.last().map_or(0, |shelf| shelf.y+shelf.height);
+        if width > self.width || height > self.height-y {
+            return None;
+        }
+        self.shelves.push(Shelf {
+            y,
+            height,
+            x: width,
+        });
+        Some([0, y])
+    }
+}
+
+struct AtlasPage {
+    texture: ImageTexture,
+    allocator: ShelfAllocator,
+}
+
+/// Where an image was packed in a [`TextureAtlas`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AtlasRegion {
+    /// Index of the page texture holding the image, see [`TextureAtlas::page`].
+    pub page: usize,
+    pub uv: UvRect,
+    /// The image's position and size on the page, in pixels.
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Packs many small images, such as icons, into a few shared textures.
+///
+/// Images on the same page can be drawn by one [`ImageRenderer`](crate::image::ImageRenderer) draw, so keep
+/// instances grouped by [`AtlasRegion::page`] and draw each group from its page. A new page is added whenever an image
+/// does not fit on the existing ones.
+pub struct TextureAtlas {
+    pages: Vec<AtlasPage>,
+    page_size: u32,
+    filter: wgpu::FilterMode,
+}
+
+impl TextureAtlas {
+    /// Pages are square, `page_size` pixels across.
+    pub fn new(page_size: u32, filter: wgpu::FilterMode) -> Self {
+        Self {
+            pages: Vec::new(),
+            page_size,
+            filter,
+        }
+    }
+
+    /// Uploads `image` to the first page with room for it. Returns `None` if it is larger than a page.
+    pub fn insert(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, image: &RgbaImage) -> Option<AtlasRegion> {
+        let (padded_width, padded_height) = (image.width+PADDING*2, image.height+PADDING*2);
+        if padded_width > self.page_size || padded_height > self.page_size {
+            return None;
+        }
+
+        let allocation = self.pages.iter_mut()
+            .enumerate()
+            .find_map(|(index, page)| page.allocator.allocate(padded_width, padded_height).map(|position| (index, position)));
+        let (page, [x, y]) = match allocation {
+            Some(allocation) => allocation,
+            None => {
+                let mut allocator = ShelfAllocator::new(self.page_size, self.page_size);
+                let position = allocator.allocate(padded_width, padded_height)?;
+                self.pages.push(AtlasPage {
+                    texture: ImageTexture::new(device, self.page_size, self.page_size, self.filter),
+                    allocator,
+                });
+                (self.pages.len()-1, position)
+            },
+        };
+
+        self.pages[page].texture.write(queue, x, y, &pad(image));
+        let (x, y) = (x+PADDING, y+PADDING);
+        Some(AtlasRegion {
+            page,
+            uv: UvRect::from_pixels(x, y, image.width, image.height, [self.page_size; 2]),
+            x,
+            y,
+            width: image.width,
+            height: image.height,
+        })
+    }
+
+    pub fn page(&self, index: usize) -> &ImageTexture {
+        &self.pages[index].texture
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.page_size
+    }
+
+    /// Forgets every image and drops the pages.
+    pub fn clear(&mut self) {
+        self.pages.clear();
+    }
+}
+
+/// `image` with [`PADDING`] added on every side, repeating its edge texels.
+fn pad(image: &RgbaImage) -> RgbaImage {
+    let (width, height) = (image.width+PADDING*2, image.height+PADDING*2);
+    if image.width == 0 || image.height == 0 {
+        return RgbaImage {
+            width,
+            height,
+            data: vec![0; (width*height*4) as usize],
+        };
+    }
+    let data = (0..height).flat_map(|y| (0..width).map(move |x| (x, y)))
+        .flat_map(|(x, y)| {
+            let source_x = x.saturating_sub(PADDING).min(image.width-1);
+            let source_y = y.saturating_sub(PADDING).min(image.height-1);
+            image.pixel(source_x, source_y)
+        })
+        .collect();
+    RgbaImage {
+        width,
+        height,
+        data,
+    }
+}
@@ -0,0 +1,59 @@
+/// How a renderer's output is combined with what is already in the target.
+///
+/// Except for [`BlendMode::PremultipliedAlpha`], colours are given with straight alpha and premultiplied by the
+/// shaders, which is what lets every mode share premultiplied blend factors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BlendMode {
+    /// Overwrites the target, ignoring alpha.
+    Replace,
+    /// Standard "over" compositing of straight-alpha colours.
+    #[default]
+    Alpha,
+    /// "Over" compositing of colours whose components are already multiplied by alpha, after sRGB encoding like every
+    /// other colour.
+    PremultipliedAlpha,
+    /// Adds the colour, weighted by alpha, to the target.
+    Additive,
+    /// Multiplies the target by the colour, weighted by alpha.
+    Multiply,
+}
+
+impl BlendMode {
+    pub fn blend_state(self) -> wgpu::BlendState {
+        let over_alpha = wgpu::BlendComponent {
+            src_factor: wgpu::BlendFactor::One,
+            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+            operation: wgpu::BlendOperation::Add,
+        };
+        match self {
+            BlendMode::Replace => wgpu::BlendState::REPLACE,
+            BlendMode::Alpha | BlendMode::PremultipliedAlpha => wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING,
+            BlendMode::Additive => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::One,
+                    dst_factor: wgpu::BlendFactor::One,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: over_alpha,
+            },
+            BlendMode::Multiply => wgpu::BlendState {
+                color: wgpu::BlendComponent {
+                    src_factor: wgpu::BlendFactor::Dst,
+                    dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                    operation: wgpu::BlendOperation::Add,
+                },
+                alpha: over_alpha,
+            },
+        }
+    }
+
+    /// Whether shaders must multiply their straight-alpha output by alpha before blending.
+    pub(crate) fn premultiplies_in_shader(self) -> bool {
+        !matches!(self, BlendMode::Replace | BlendMode::PremultipliedAlpha)
+    }
+
+    /// Whether the colours given to shaders are already premultiplied.
+    pub(crate) fn premultiplied_input(self) -> bool {
+        self == BlendMode::PremultipliedAlpha
+    }
+}
@@ -1,17 +1,17 @@
-use crate::rect::*;
+use crate::{
+    color::Color,
+    rect::*,
+};
 
 pub const THICK: f32 = 0.25;
 pub const SPACING: f32 = 0.25;
-const R: f32 = 1.0;
-const G: f32 = 1.0;
-const B: f32 = 1.0;
-const A: f32 = 1.0;
 
 pub struct FillText<'a> {
     pub text: &'a str,
     pub placement_area: SizeAndCenter,
     pub resx: f32,
     pub resy: f32,
+    pub color: Color,
 }
 
 impl From<FillText<'_>> for Vec<RectRaw> {
@@ -348,7 +348,10 @@ impl From<FillText<'_>> for Vec<RectRaw> {
             rects.push(RectRaw {
                 scale: [size_and_center.sx, size_and_center.sy],
                 translation: [size_and_center.cx+target.cx, size_and_center.cy+target.cy],
-                color: [R, G, B, A],
+                fill: fill_text.color.into(),
+                corner_radii: [0.0; 4],
+                stroke_color: [0.0; 4],
+                stroke: [0.0; 2],
             })
         }
 
@@ -0,0 +1,94 @@
+use crate::rect::Points;
+
+/// A region of the framebuffer in pixels, from the top left, that drawing is limited to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScissorRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ScissorRect {
+    /// The pixels whose centres lie inside `points`, on a `width` by `height` framebuffer.
+    pub fn from_points(points: Points, width: u32, height: u32) -> Self {
+        let to_x = |x: f32| (((x+1.0)/2.0*width as f32).round().max(0.0) as u32).min(width);
+        let to_y = |y: f32| (((1.0-y)/2.0*height as f32).round().max(0.0) as u32).min(height);
+        let (x1, y1) = (to_x(points.p1x), to_y(points.p1y));
+        let (x2, y2) = (to_x(points.p2x).max(x1), to_y(points.p2y).max(y1));
+        Self {
+            x: x1,
+            y: y1,
+            width: x2-x1,
+            height: y2-y1,
+        }
+    }
+
+    /// Whether no pixels are left to draw into, as when nested clips do not overlap or a clip lies off the target.
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub(crate) fn apply(self, render_pass: &mut wgpu::RenderPass) {
+        render_pass.set_scissor_rect(self.x, self.y, self.width, self.height);
+    }
+}
+
+/// Nested clip rects, such as for scroll views inside panels. Each pushed rect is intersected with the one below it,
+/// so drawing is limited to the region every rect on the stack shares.
+///
+/// Apply the current clip to a [`RenderTarget`](crate::target::RenderTarget) with
+/// [`RenderTarget::with_scissor`](crate::target::RenderTarget::with_scissor) to have any renderer scissor to it.
+#[derive(Debug, Clone, Default)]
+pub struct ClipStack {
+    stack: Vec<Points>,
+}
+
+impl ClipStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<P: Into<Points>>(&mut self, clip: P) {
+        let clip = clip.into();
+        let clip = match self.current() {
+            Some(current) => intersect(current, clip),
+            None => clip,
+        };
+        self.stack.push(clip);
+    }
+
+    /// Removes the most recently pushed rect, returning it as it was intersected.
+    pub fn pop(&mut self) -> Option<Points> {
+        self.stack.pop()
+    }
+
+    /// The region drawing is limited to, or `None` when nothing is pushed.
+    pub fn current(&self) -> Option<Points> {
+        self.stack.last().copied()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.stack.clear();
+    }
+
+    /// The current clip on a `width` by `height` framebuffer, or `None` when nothing is pushed. It can be
+    /// [empty](ScissorRect::is_empty), which renderers take as nothing to draw.
+    pub fn scissor_rect(&self, width: u32, height: u32) -> Option<ScissorRect> {
+        self.current().map(|clip| ScissorRect::from_points(clip, width, height))
+    }
+}
+
+/// The overlap of two rects, which is empty (`p2` not right of and below `p1`) when they do not overlap.
+pub fn intersect(a: Points, b: Points) -> Points {
+    Points {
+        p1x: a.p1x.max(b.p1x),
+        p1y: a.p1y.min(b.p1y),
+        p2x: a.p2x.min(b.p2x),
+        p2y: a.p2y.max(b.p2y),
+    }
+}
@@ -0,0 +1,100 @@
+// Prepended to every colour-writing shader by shader.rs, after `SRGB_TARGET`, `PREMULTIPLY_OUTPUT` and
+// `PREMULTIPLIED_INPUT` are declared.
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if (c <= 0.04045) {
+        return c/12.92;
+    }
+    return pow((c+0.055)/1.055, 2.4);
+}
+
+// Colours arrive sRGB encoded. sRGB targets encode on write, so they need linear values. Premultiplied colours are
+// linearised unpremultiplied, as the encoding is not linear.
+// Most blend modes expect premultiplied output, see blend.rs.
+fn output_color(color: vec4<f32>) -> vec4<f32> {
+    var out = color;
+    if (SRGB_TARGET) {
+        var rgb = color.rgb;
+        if (PREMULTIPLIED_INPUT && color.a > 0.0) {
+            rgb = rgb/color.a;
+        }
+        rgb = vec3<f32>(srgb_to_linear(rgb.r), srgb_to_linear(rgb.g), srgb_to_linear(rgb.b));
+        if (PREMULTIPLIED_INPUT) {
+            rgb = rgb*color.a;
+        }
+        out = vec4<f32>(rgb, color.a);
+    }
+    if (PREMULTIPLY_OUTPUT) {
+        out = vec4<f32>(out.rgb*out.a, out.a);
+    }
+    return out;
+}
+
+// Fraction of a pixel covered by a shape, given the signed distance in pixels from the pixel's centre to the shape's
+// edge. Exact for straight edges, which is close enough for curves a few pixels across or more.
+fn coverage(distance: f32) -> f32 {
+    return clamp(0.5 - distance, 0.0, 1.0);
+}
+
+fn apply_coverage(color: vec4<f32>, coverage: f32) -> vec4<f32> {
+    if (PREMULTIPLIED_INPUT) {
+        return color*coverage;
+    }
+    return vec4<f32>(color.rgb, color.a*coverage);
+}
+
+// Interpolates between two input colours as if they were premultiplied, so a transparent colour does not darken the
+// other.
+fn mix_colors(a: vec4<f32>, b: vec4<f32>, t: f32) -> vec4<f32> {
+    if (PREMULTIPLIED_INPUT) {
+        return mix(a, b, t);
+    }
+    let alpha = mix(a.a, b.a, t);
+    if (alpha == 0.0) {
+        return vec4<f32>(0.0);
+    }
+    return vec4<f32>(mix(a.rgb*a.a, b.rgb*b.a, t)/alpha, alpha);
+}
+
+// Signed distance in pixels from `p` to the edge of a rect centred on the origin, negative inside. `p` is y down and
+// the radii go top left, top right, bottom right, bottom left.
+fn rounded_rect_distance(p: vec2<f32>, half_size: vec2<f32>, radii: vec4<f32>) -> f32 {
+    var r = radii.w;
+    if (p.y < 0.0) {
+        r = select(radii.y, radii.x, p.x < 0.0);
+    } else if (p.x > 0.0) {
+        r = radii.z;
+    }
+    r = min(r, min(half_size.x, half_size.y));
+    let q = abs(p)-half_size+r;
+    return length(max(q, vec2<f32>(0.0)))+min(max(q.x, q.y), 0.0)-r;
+}
+
+// How far `t` is from stop `a` to stop `b`, jumping straight across stops at the same offset.
+fn stop_progress(t: f32, a: f32, b: f32) -> f32 {
+    if (b <= a) {
+        return select(0.0, 1.0, t >= b);
+    }
+    return clamp((t - a)/(b - a), 0.0, 1.0);
+}
+
+// Colour of a fill (see fill.rs) at `p`, in pixels from the centre of a shape with the given half size, y down.
+fn fill_color(p: vec2<f32>, half_size: vec2<f32>, shape: vec4<f32>, c0: vec4<f32>, c1: vec4<f32>, c2: vec4<f32>, c3: vec4<f32>, offsets: vec4<f32>) -> vec4<f32> {
+    var t: f32;
+    if (shape.x == 1.0) {
+        let direction = vec2<f32>(cos(shape.y), -sin(shape.y));
+        let half_length = abs(half_size.x*direction.x)+abs(half_size.y*direction.y);
+        t = dot(p, direction)/max(half_length*2.0, 0.0001)+0.5;
+    } else if (shape.x == 2.0) {
+        let relative = p/max(half_size, vec2<f32>(0.0001))*0.5+0.5;
+        t = length((relative-shape.yz)*2.0)/max(shape.w, 0.0001);
+    } else {
+        return c0;
+    }
+
+    var color = c0;
+    color = mix_colors(color, c1, stop_progress(t, offsets.x, offsets.y));
+    color = mix_colors(color, c2, stop_progress(t, offsets.y, offsets.z));
+    color = mix_colors(color, c3, stop_progress(t, offsets.z, offsets.w));
+    return color;
+}
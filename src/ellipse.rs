@@ -1,25 +1,37 @@
-use wgpu::{BufferAddress, include_wgsl};
+use std::ops::Range;
+
+use wgpu::BufferAddress;
 use crate::{
+    blend::BlendMode,
+    fill::{Fill, FillRaw},
+    instance_buffer::InstanceBuffer,
+    mask,
     resolution_buffer::ResolutionBuffer,
+    shader,
+    stroke::Stroke,
+    target::RenderTarget,
 };
 
 pub struct EllipseRenderer {
-    ellipse_buffer: wgpu::Buffer,
-    ellipse_count: u32,
+    ellipse_buffer: InstanceBuffer<EllipseBuffer>,
     pipeline: wgpu::RenderPipeline,
     resolution_bind_group: wgpu::BindGroup,
 }
 
 impl EllipseRenderer {
-    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, resolution_buffer: &ResolutionBuffer, max_ellipse_count: u64) -> Self {
-        let shader = device.create_shader_module(include_wgsl!("ellipse.wgsl"));
-
-        let ellipse_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Ellipse Buffer"),
-            size: ELLIPSE_BUFFER_SIZE*max_ellipse_count,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Ellipse Shader", include_str!("ellipse.wgsl"), texture_format, blend_mode);
+
+        let ellipse_buffer = InstanceBuffer::new(device, "Ellipse Buffer");
 
         let ellipse_layout = wgpu::VertexBufferLayout {
             array_stride: ELLIPSE_BUFFER_SIZE,
@@ -36,39 +48,50 @@ impl EllipseRenderer {
                     format: wgpu::VertexFormat::Float32x2
                 },
                 wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress*2,
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 16]>() as BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 20]>() as BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 24]>() as BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 28]>() as BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 32]>() as BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x2
                 }
             ]
         };
 
-        let resolution_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("Ellipse Resolution Bind Group Layout"),
-            entries: &[
-                wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::FRAGMENT,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }
-            ]
-        });
-
-        let resolution_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Ellipse Resolution Bind Group"),
-            layout: &resolution_bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry {
-                    binding: 0,
-                    resource: resolution_buffer.binding(),
-                }
-            ],
-        });
+        let resolution_bind_group_layout = ResolutionBuffer::bind_group_layout(device);
+        let resolution_bind_group = resolution_buffer.bind_group(device, &resolution_bind_group_layout);
 
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Ellipse Pipeline"),
@@ -91,7 +114,7 @@ impl EllipseRenderer {
                 entry_point: "frag_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: texture_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    blend: Some(blend_mode.blend_state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })]
             }),
@@ -104,9 +127,9 @@ impl EllipseRenderer {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -115,46 +138,50 @@ impl EllipseRenderer {
 
         Self {
             ellipse_buffer,
-            ellipse_count: 0,
             pipeline,
             resolution_bind_group
         }
     }
 
-    pub fn set_ellipse_buffer(&mut self, queue: &wgpu::Queue, data: &[EllipseBuffer]) {
-        self.ellipse_count = data.len() as u32;
-        queue.write_buffer(&self.ellipse_buffer, 0, bytemuck::cast_slice::<EllipseBuffer, u8>(data));
+    pub fn set_ellipse_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[EllipseBuffer]) {
+        self.ellipse_buffer.set(device, queue, data);
+    }
+
+    pub fn ellipse_buffer(&self) -> &InstanceBuffer<EllipseBuffer> {
+        &self.ellipse_buffer
     }
 
-    pub fn render_all(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, loadop: wgpu::LoadOp<wgpu::Color>) {
+    pub fn ellipse_buffer_mut(&mut self) -> &mut InstanceBuffer<EllipseBuffer> {
+        &mut self.ellipse_buffer
+    }
+
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        let target = target.into();
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Menu Render Pass"),
             color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: loadop,
-                        store: true,
-                    }
-                })
+                Some(target.color_attachment(loadop))
             ],
             depth_stencil_attachment: None,
         });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, 0..self.ellipse_buffer.len());
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
         render_pass.set_pipeline(&self.pipeline);
         render_pass.set_bind_group(0, &self.resolution_bind_group, &[]);
-        render_pass.set_vertex_buffer(0, self.ellipse_buffer.slice(..));
-        render_pass.draw(0..6, 0..self.ellipse_count);
+        render_pass.set_vertex_buffer(0, self.ellipse_buffer.buffer().slice(..));
+        render_pass.draw(0..6, instances);
     }
 }
 
 #[derive(Debug)]
 pub struct EllipseDescriptor {
     pub sizing: crate::rect::SizeAndCenter,
-    pub r: f32,
-    pub g: f32,
-    pub b: f32,
-    pub a: f32,
+    pub fill: Fill,
+    pub stroke: Stroke,
 }
 
 const ELLIPSE_BUFFER_SIZE: BufferAddress = std::mem::size_of::<EllipseBuffer>() as BufferAddress;
@@ -164,7 +191,9 @@ const ELLIPSE_BUFFER_SIZE: BufferAddress = std::mem::size_of::<EllipseBuffer>()
 pub struct EllipseBuffer {
     scale: [f32; 2],
     translation: [f32; 2],
-    color: [f32; 4],
+    fill: FillRaw,
+    stroke_color: [f32; 4],
+    stroke: [f32; 2],
 }
 
 impl From<EllipseDescriptor> for EllipseBuffer {
@@ -172,7 +201,9 @@ impl From<EllipseDescriptor> for EllipseBuffer {
         Self {
             scale: [descriptor.sizing.sx, descriptor.sizing.sy],
             translation: [descriptor.sizing.cx, descriptor.sizing.cy],
-            color: [descriptor.r, descriptor.g, descriptor.b, descriptor.a]
+            fill: descriptor.fill.into(),
+            stroke_color: descriptor.stroke.color.into(),
+            stroke: descriptor.stroke.width_and_outset(),
         }
     }
 }
\ No newline at end of file
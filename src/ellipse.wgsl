@@ -8,14 +8,30 @@ var<uniform> res: Vec2f32;
 struct Instance {
     @location(0) scale: vec2<f32>,
     @location(1) translation: vec2<f32>,
-    @location(2) color: vec4<f32>,
+    // See fill.rs.
+    @location(2) fill_shape: vec4<f32>,
+    @location(3) fill_color0: vec4<f32>,
+    @location(4) fill_color1: vec4<f32>,
+    @location(5) fill_color2: vec4<f32>,
+    @location(6) fill_color3: vec4<f32>,
+    @location(7) fill_offsets: vec4<f32>,
+    @location(8) stroke_color: vec4<f32>,
+    // Width and how much of it lies outside the ellipse, both in pixels.
+    @location(9) stroke: vec2<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) scale: vec2<f32>,
     @location(1) translation: vec2<f32>,
-    @location(2) color: vec4<f32>,
+    @location(2) fill_shape: vec4<f32>,
+    @location(3) fill_color0: vec4<f32>,
+    @location(4) fill_color1: vec4<f32>,
+    @location(5) fill_color2: vec4<f32>,
+    @location(6) fill_color3: vec4<f32>,
+    @location(7) fill_offsets: vec4<f32>,
+    @location(8) stroke_color: vec4<f32>,
+    @location(9) stroke: vec2<f32>,
 };
 
 // Vertex shader
@@ -26,45 +42,68 @@ fn vert_main(
     instance: Instance,
 ) -> VertexOutput {
     var out: VertexOutput;
+    // Grow the quad to cover any part of the stroke outside the ellipse, plus a pixel for the anti-aliased edge.
+    let quad_scale = instance.scale+(instance.stroke.y+1.0)*2.0/res.inner;
     switch (vertex_index) {
         case 0u, 3u: {
-            out.position = vec4<f32>(vec2<f32>(-1.0, -1.0)*instance.scale+instance.translation, 0.0, 1.0);
+            out.position = vec4<f32>(vec2<f32>(-1.0, -1.0)*quad_scale+instance.translation, 0.0, 1.0);
         }
         case 1u: {
-            out.position = vec4<f32>(vec2<f32>(1.0, -1.0)*instance.scale+instance.translation, 0.0, 1.0);
+            out.position = vec4<f32>(vec2<f32>(1.0, -1.0)*quad_scale+instance.translation, 0.0, 1.0);
         }
         case 5u: {
-            out.position = vec4<f32>(vec2<f32>(-1.0, 1.0)*instance.scale+instance.translation, 0.0, 1.0);
+            out.position = vec4<f32>(vec2<f32>(-1.0, 1.0)*quad_scale+instance.translation, 0.0, 1.0);
         }
         // case 2, 4
         default: {
-            out.position = vec4<f32>(vec2<f32>(1.0, 1.0)*instance.scale+instance.translation, 0.0, 1.0);
+            out.position = vec4<f32>(vec2<f32>(1.0, 1.0)*quad_scale+instance.translation, 0.0, 1.0);
         }
     }
     out.scale = instance.scale;
     out.translation = instance.translation;
-    out.color = instance.color;
+    out.fill_shape = instance.fill_shape;
+    out.fill_color0 = instance.fill_color0;
+    out.fill_color1 = instance.fill_color1;
+    out.fill_color2 = instance.fill_color2;
+    out.fill_color3 = instance.fill_color3;
+    out.fill_offsets = instance.fill_offsets;
+    out.stroke_color = instance.stroke_color;
+    out.stroke = instance.stroke;
     return out;
 }
 
 // Fragment shader
 
+// Approximate signed distance in pixels from `p` to the edge of an ellipse centred on the origin, negative inside.
+// Exact on the edge itself, which is all the strokes and anti-aliasing need.
+fn ellipse_distance(p: vec2<f32>, radii: vec2<f32>) -> f32 {
+    let k0 = length(p/radii);
+    let k1 = length(p/(radii*radii));
+    if (k1 == 0.0) {
+        return -min(radii.x, radii.y);
+    }
+    return k0*(k0 - 1.0)/k1;
+}
+
 @fragment
 fn frag_main(
     in: VertexOutput,
 ) -> @location(0) vec4<f32> {
     let res = res.inner;
-    let pos = in.position.xy/res*vec2<f32>(2.0, -2.0)+vec2<f32>(-1.0, 1.0);
-    let scale = in.scale;
-    let translation = in.translation;
-    var col = in.color;
+    let center = (in.translation*vec2<f32>(0.5, -0.5)+0.5)*res;
+    let radii = in.scale*res*0.5;
 
-    let normalized_and_translated = (translation-pos)/scale;
-    let distcvec = normalized_and_translated*normalized_and_translated;
-
-    if (distcvec.x+distcvec.y > 1.0) {
+    let p = in.position.xy-center;
+    let distance = ellipse_distance(p, radii);
+    let outer_edge = in.stroke.y;
+    let shape_coverage = coverage(distance-outer_edge);
+    if (shape_coverage <= 0.0) {
         discard;
     }
 
-    return col;
+    var color = fill_color(p, radii, in.fill_shape, in.fill_color0, in.fill_color1, in.fill_color2, in.fill_color3, in.fill_offsets);
+    if (in.stroke.x > 0.0) {
+        color = mix_colors(color, in.stroke_color, coverage(outer_edge-in.stroke.x-distance));
+    }
+    return output_color(apply_coverage(color, shape_coverage));
 }
@@ -0,0 +1,69 @@
+use std::fmt;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug)]
+pub enum Error {
+    /// No adapter matched the requested backends and options.
+    NoAdapter,
+    RequestDevice(wgpu::RequestDeviceError),
+    /// The surface reports no formats it can be configured with on the chosen adapter.
+    NoSupportedSurfaceFormat,
+    UnsupportedReadbackFormat(wgpu::TextureFormat),
+    BufferMap(wgpu::BufferAsyncError),
+    /// A string could not be parsed as a hex colour or CSS colour name.
+    InvalidColor(String),
+    /// The sample count is not 1, 2, 4 or 8, or the adapter cannot multisample the target format.
+    UnsupportedSampleCount(u32),
+    /// A gradient was given no colour stops, or more than [`MAX_GRADIENT_STOPS`](crate::fill::MAX_GRADIENT_STOPS).
+    UnsupportedGradientStopCount(usize),
+    /// Image bytes could not be decoded.
+    ImageDecode(String),
+    /// The renderer draws offscreen or into an external target and has no surface.
+    NoSurface,
+    /// The renderer has no offscreen target to render into and read back.
+    NotHeadless,
+    Surface(wgpu::SurfaceError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoAdapter => write!(f, "no suitable graphics adapter found"),
+            Error::RequestDevice(e) => write!(f, "could not request device: {}", e),
+            Error::NoSupportedSurfaceFormat => write!(f, "surface is incompatible with the adapter"),
+            Error::UnsupportedReadbackFormat(format) => write!(f, "cannot read back textures with format {:?}", format),
+            Error::BufferMap(e) => write!(f, "could not map buffer: {}", e),
+            Error::InvalidColor(color) => write!(f, "invalid colour '{}'", color),
+            Error::UnsupportedSampleCount(count) => write!(f, "unsupported sample count {}", count),
+            Error::UnsupportedGradientStopCount(count) => write!(f, "unsupported gradient stop count {}", count),
+            Error::ImageDecode(e) => write!(f, "could not decode image: {}", e),
+            Error::NoSurface => write!(f, "renderer has no surface"),
+            Error::NotHeadless => write!(f, "renderer has no offscreen target"),
+            Error::Surface(e) => write!(f, "could not get surface texture: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::RequestDevice(e) => Some(e),
+            Error::BufferMap(e) => Some(e),
+            Error::Surface(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<wgpu::RequestDeviceError> for Error {
+    fn from(e: wgpu::RequestDeviceError) -> Self { Error::RequestDevice(e) }
+}
+
+impl From<wgpu::BufferAsyncError> for Error {
+    fn from(e: wgpu::BufferAsyncError) -> Self { Error::BufferMap(e) }
+}
+
+impl From<wgpu::SurfaceError> for Error {
+    fn from(e: wgpu::SurfaceError) -> Self { Error::Surface(e) }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    color::Color,
+    error::Error,
+};
+
+/// The most colour stops a gradient can have; it all has to fit in the instance data.
+pub const MAX_GRADIENT_STOPS: usize = 4;
+
+/// How the inside of a shape is painted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fill {
+    Solid(Color),
+    Gradient(Gradient),
+}
+
+impl From<Color> for Fill {
+    fn from(color: Color) -> Self {
+        Fill::Solid(color)
+    }
+}
+
+impl From<Gradient> for Fill {
+    fn from(gradient: Gradient) -> Self {
+        Fill::Gradient(gradient)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    /// Position along the gradient, from 0 at its start to 1 at its end.
+    pub offset: f32,
+    pub color: Color,
+}
+
+impl ColorStop {
+    pub const fn new(offset: f32, color: Color) -> Self {
+        Self {
+            offset,
+            color,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientShape {
+    /// `angle` is in radians, counter-clockwise from left to right. Like CSS, the gradient runs through the shape's
+    /// centre and is just long enough for its ends to touch the corners of the shape's bounds.
+    Linear { angle: f32 },
+    /// `center` is relative to the shape's bounds, with (0, 0) the top left and (1, 1) the bottom right. `radius` is in
+    /// half extents, so 1 reaches the sides of the bounds from their centre and stretches with the shape.
+    Radial { center: [f32; 2], radius: f32 },
+}
+
+/// Colours are interpolated in sRGB like CSS does, between stops sorted by offset. Before the first stop and after the
+/// last the end colours continue.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Gradient {
+    pub shape: GradientShape,
+    stops: [ColorStop; MAX_GRADIENT_STOPS],
+    stop_count: usize,
+}
+
+impl Gradient {
+    /// Fails if there are no stops or more than [`MAX_GRADIENT_STOPS`].
+    pub fn new(shape: GradientShape, stops: &[ColorStop]) -> Result<Self, Error> {
+        if stops.is_empty() || stops.len() > MAX_GRADIENT_STOPS {
+            return Err(Error::UnsupportedGradientStopCount(stops.len()));
+        }
+        let mut padded = [stops[stops.len()-1]; MAX_GRADIENT_STOPS];
+        padded[..stops.len()].copy_from_slice(stops);
+        Ok(Self {
+            shape,
+            stops: padded,
+            stop_count: stops.len(),
+        })
+    }
+
+    pub fn linear(angle: f32, stops: &[ColorStop]) -> Result<Self, Error> {
+        Self::new(GradientShape::Linear { angle }, stops)
+    }
+
+    pub fn radial(center: [f32; 2], radius: f32, stops: &[ColorStop]) -> Result<Self, Error> {
+        Self::new(GradientShape::Radial { center, radius }, stops)
+    }
+
+    pub fn stops(&self) -> &[ColorStop] {
+        &self.stops[..self.stop_count]
+    }
+}
+
+/// A [`Fill`] as the shaders take it, see `fill_color` in common.wgsl.
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct FillRaw {
+    /// Kind (0 solid, 1 linear, 2 radial) followed by the linear angle or the radial centre and radius.
+    pub shape: [f32; 4],
+    pub colors: [[f32; 4]; MAX_GRADIENT_STOPS],
+    pub offsets: [f32; MAX_GRADIENT_STOPS],
+}
+
+impl From<Color> for FillRaw {
+    fn from(color: Color) -> Self {
+        Self {
+            shape: [0.0; 4],
+            colors: [color.into(); MAX_GRADIENT_STOPS],
+            offsets: [0.0; MAX_GRADIENT_STOPS],
+        }
+    }
+}
+
+impl From<Fill> for FillRaw {
+    fn from(fill: Fill) -> Self {
+        let gradient = match fill {
+            Fill::Solid(color) => return color.into(),
+            Fill::Gradient(gradient) => gradient,
+        };
+        let shape = match gradient.shape {
+            GradientShape::Linear { angle } => [1.0, angle, 0.0, 0.0],
+            GradientShape::Radial { center, radius } => [2.0, center[0], center[1], radius],
+        };
+        let mut colors = [[0.0; 4]; MAX_GRADIENT_STOPS];
+        let mut offsets = [0.0; MAX_GRADIENT_STOPS];
+        // Out of order offsets are pulled up to the one before, as CSS does.
+        let mut previous = f32::NEG_INFINITY;
+        for (i, stop) in gradient.stops.iter().enumerate() {
+            colors[i] = stop.color.into();
+            offsets[i] = stop.offset.max(previous);
+            previous = offsets[i];
+        }
+        Self {
+            shape,
+            colors,
+            offsets,
+        }
+    }
+}
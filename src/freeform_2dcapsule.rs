@@ -1,21 +1,37 @@
-use wgpu::{include_wgsl, BufferAddress};
+use std::ops::Range;
+
+use wgpu::BufferAddress;
+
+use crate::{
+    blend::BlendMode,
+    color::Color,
+    instance_buffer::InstanceBuffer,
+    mask,
+    resolution_buffer::ResolutionBuffer,
+    shader,
+    target::RenderTarget,
+};
 
 pub struct Freeform2DCapsuleRenderer {
-    capsule_buffer: wgpu::Buffer,
-    capsule_count: u32,
+    capsule_buffer: InstanceBuffer<Freeform2DCapsule>,
     pipeline: wgpu::RenderPipeline,
+    resolution_bind_group: wgpu::BindGroup,
 }
 
 impl Freeform2DCapsuleRenderer {
-    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, max_capsule_count: u64) -> Self {
-        let shader = device.create_shader_module(include_wgsl!("freeform_2dcapsule.wgsl"));
-
-        let line_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Freeform 2D Capsule Buffer"),
-            size: FREEFORM2DCAPSULE_SIZE*max_capsule_count,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Freeform 2D Capsule Shader", include_str!("freeform_2dcapsule.wgsl"), texture_format, blend_mode);
+
+        let line_buffer = InstanceBuffer::new(device, "Freeform 2D Capsule Buffer");
 
         let line_layout = wgpu::VertexBufferLayout {
             array_stride: FREEFORM2DCAPSULE_SIZE,
@@ -44,11 +60,16 @@ impl Freeform2DCapsuleRenderer {
             ]
         };
 
+        let resolution_bind_group_layout = ResolutionBuffer::bind_group_layout(device);
+        let resolution_bind_group = resolution_buffer.bind_group(device, &resolution_bind_group_layout);
+
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Freeform 2D Capsule Pipeline"),
             layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Freeform 2D Capsule Pipeline Layout"),
-                bind_group_layouts: &[],
+                bind_group_layouts: &[
+                    &resolution_bind_group_layout
+                ],
                 push_constant_ranges: &[],
             })),
             vertex: wgpu::VertexState {
@@ -63,7 +84,7 @@ impl Freeform2DCapsuleRenderer {
                 entry_point: "frag_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: texture_format,
-                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    blend: Some(blend_mode.blend_state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })]
             }),
@@ -76,9 +97,9 @@ impl Freeform2DCapsuleRenderer {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -87,34 +108,42 @@ impl Freeform2DCapsuleRenderer {
 
         Self {
             capsule_buffer: line_buffer,
-            capsule_count: 0,
             pipeline,
+            resolution_bind_group,
         }
     }
 
-    pub fn set_capsule_buffer(&mut self, queue: &wgpu::Queue, data: &[Freeform2DCapsule]) {
-        self.capsule_count = data.len() as u32;
-        queue.write_buffer(&self.capsule_buffer, 0, bytemuck::cast_slice::<Freeform2DCapsule, u8>(data));
+    pub fn set_capsule_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[Freeform2DCapsule]) {
+        self.capsule_buffer.set(device, queue, data);
+    }
+
+    pub fn capsule_buffer(&self) -> &InstanceBuffer<Freeform2DCapsule> {
+        &self.capsule_buffer
+    }
+
+    pub fn capsule_buffer_mut(&mut self) -> &mut InstanceBuffer<Freeform2DCapsule> {
+        &mut self.capsule_buffer
     }
 
-    pub fn render_all(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, loadop: wgpu::LoadOp<wgpu::Color>) {
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        let target = target.into();
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Freeform 2D Capsule Render Pass"),
             color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: loadop,
-                        store: true,
-                    }
-                })
+                Some(target.color_attachment(loadop))
             ],
             depth_stencil_attachment: None,
         });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, 0..self.capsule_buffer.len());
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_vertex_buffer(0, self.capsule_buffer.slice(..));
-        render_pass.draw(0..4, 0..self.capsule_count);
+        render_pass.set_bind_group(0, &self.resolution_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.capsule_buffer.buffer().slice(..));
+        render_pass.draw(0..4, instances);
     }
 }
 
@@ -125,6 +154,7 @@ pub const FREEFORM2DCAPSULE_SIZE: BufferAddress = std::mem::size_of::<Freeform2D
 pub struct Freeform2DCapsule {
     pub p1: [f32; 2],
     pub p2: [f32; 2],
-    pub radius: f32,
-    pub color: [f32; 4],
+    /// In pixels, so the ends stay round at any aspect ratio.
+    pub radius_px: f32,
+    pub color: Color,
 }
\ No newline at end of file
@@ -15,7 +15,20 @@ struct Instance {
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    // Everything below is in framebuffer pixels.
+    @location(1) p1: vec2<f32>,
+    @location(2) p2: vec2<f32>,
+    @location(3) radius: f32,
 };
+
+fn to_pixels(p: vec2<f32>) -> vec2<f32> {
+    return (p*vec2<f32>(0.5, -0.5)+0.5)*res.inner;
+}
+
+fn from_pixels(p: vec2<f32>) -> vec2<f32> {
+    return (p/res.inner - 0.5)*vec2<f32>(2.0, -2.0);
+}
+
 // Vertex shader
 
 @vertex
@@ -23,28 +36,38 @@ fn vert_main(
     @builtin(vertex_index) vertex_index: u32,
     instance: Instance,
 ) -> VertexOutput {
-    var pi = 3.14159; // TODO: not this
     var out: VertexOutput;
-    let angle = atan((instance.p2.y-instance.p1.y)/(instance.p2.x-instance.p1.x));
-    let rotated = angle+pi/2.0;
+    let p1 = to_pixels(instance.p1);
+    let p2 = to_pixels(instance.p2);
+
+    var direction = vec2<f32>(1.0, 0.0);
+    if (any(p1 != p2)) {
+        direction = normalize(p2-p1);
+    }
+    // A pixel of margin for the anti-aliased edge.
+    let along = direction*(instance.radius+1.0);
+    let across = vec2<f32>(-along.y, along.x);
+
+    var corner: vec2<f32>;
     switch (vertex_index) {
         case 0u: {
-            out.position = vec4<f32>(instance.p2+vec2<f32>(instance.radius*(cos(angle)+cos(rotated)), instance.radius*(sin(angle)+sin(rotated))), 0.0, 1.0);
+            corner = p2+along+across;
         }
         case 1u: {
-            out.position = vec4<f32>(instance.p1+vec2<f32>(instance.radius*(-cos(angle)+cos(rotated)), instance.radius*(-sin(angle)+sin(rotated))), 0.0, 1.0);
+            corner = p1-along+across;
         }
         case 2u: {
-            out.position = vec4<f32>(instance.p2+vec2<f32>(instance.radius*(cos(angle)-cos(rotated)), instance.radius*(sin(angle)-sin(rotated))), 0.0, 1.0);
-        }
-        case 3u: {
-            out.position = vec4<f32>(instance.p1+vec2<f32>(instance.radius*(-cos(angle)-cos(rotated)), instance.radius*(-sin(angle)-sin(rotated))), 0.0, 1.0);
+            corner = p2+along-across;
         }
         default: {
-            
+            corner = p1-along-across;
         }
     }
+    out.position = vec4<f32>(from_pixels(corner), 0.0, 1.0);
     out.color = instance.color;
+    out.p1 = p1;
+    out.p2 = p2;
+    out.radius = instance.radius;
     return out;
 }
 
@@ -54,5 +77,17 @@ fn vert_main(
 fn frag_main(
     in: VertexOutput,
 ) -> @location(0) vec4<f32> {
-    return in.color;
+    let to_point = in.position.xy-in.p1;
+    let segment = in.p2-in.p1;
+    var along = 0.0;
+    if (any(segment != vec2<f32>(0.0))) {
+        along = clamp(dot(to_point, segment)/dot(segment, segment), 0.0, 1.0);
+    }
+    let distance = length(to_point-segment*along)-in.radius;
+
+    let shape_coverage = coverage(distance);
+    if (shape_coverage <= 0.0) {
+        discard;
+    }
+    return output_color(apply_coverage(in.color, shape_coverage));
 }
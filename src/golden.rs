@@ -0,0 +1,216 @@
+use std::{
+    fmt,
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+use log::warn;
+
+use crate::{
+    error::{Error, Result},
+    readback::RgbaImage,
+    renderer::Renderer,
+    target::RenderTarget,
+};
+
+/// Setting this environment variable to anything records the rendered output as the reference, overwriting any
+/// existing one.
+pub const BLESS_ENV: &str = "BUI_BLESS";
+
+/// Compares rendered frames against reference PNGs.
+///
+/// References live in `reference_dir` as `<name>.png`. When a comparison fails the rendered frame and a diff
+/// image highlighting mismatched pixels in red are written to `output_dir` as `<name>.actual.png` and
+/// `<name>.diff.png`. A missing reference fails the check, unless [`BLESS_ENV`] is set to record it.
+pub struct Golden {
+    pub reference_dir: PathBuf,
+    pub output_dir: PathBuf,
+    pub tolerance: u8,
+    pub max_mismatched_pixels: usize,
+}
+
+impl Golden {
+    pub fn new<R: Into<PathBuf>, O: Into<PathBuf>>(reference_dir: R, output_dir: O) -> Self {
+        Self {
+            reference_dir: reference_dir.into(),
+            output_dir: output_dir.into(),
+            tolerance: 2,
+            max_mismatched_pixels: 0,
+        }
+    }
+
+    pub fn check(&self, name: &str, actual: &RgbaImage) -> std::result::Result<(), GoldenError> {
+        let reference_path = self.reference_dir.join(format!("{}.png", name));
+
+        if std::env::var_os(BLESS_ENV).is_some() {
+            warn!("Recording golden reference {}", reference_path.display());
+            fs::create_dir_all(&self.reference_dir)?;
+            actual.save_png(&reference_path)?;
+            return Ok(());
+        }
+        if !reference_path.exists() {
+            self.write_failure(name, actual, None)?;
+            return Err(GoldenError::MissingReference {
+                name: name.to_string(),
+                path: reference_path,
+            });
+        }
+
+        let reference = load_png(&reference_path)?;
+        let comparison = match compare(name, &reference, actual, self.tolerance) {
+            Ok(comparison) => comparison,
+            Err(e) => {
+                self.write_failure(name, actual, None)?;
+                return Err(e);
+            },
+        };
+        if comparison.mismatched_pixels > self.max_mismatched_pixels {
+            let diff_path = self.write_failure(name, actual, Some(&comparison.diff))?;
+            return Err(GoldenError::Mismatch {
+                name: name.to_string(),
+                mismatched_pixels: comparison.mismatched_pixels,
+                diff_path,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn write_failure(&self, name: &str, actual: &RgbaImage, diff: Option<&RgbaImage>) -> std::result::Result<PathBuf, GoldenError> {
+        fs::create_dir_all(&self.output_dir)?;
+        actual.save_png(self.output_dir.join(format!("{}.actual.png", name)))?;
+        let diff_path = self.output_dir.join(format!("{}.diff.png", name));
+        if let Some(diff) = diff {
+            diff.save_png(&diff_path)?;
+        }
+        Ok(diff_path)
+    }
+}
+
+pub struct Comparison {
+    pub mismatched_pixels: usize,
+    pub diff: RgbaImage,
+}
+
+/// Compares two equally sized images channel by channel, naming the scene `name` in errors.
+///
+/// The diff shows mismatched pixels in red over a dimmed greyscale copy of `actual`.
+pub fn compare(name: &str, expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> std::result::Result<Comparison, GoldenError> {
+    if expected.width != actual.width || expected.height != actual.height {
+        return Err(GoldenError::SizeMismatch {
+            name: name.to_string(),
+            expected: (expected.width, expected.height),
+            actual: (actual.width, actual.height),
+        });
+    }
+
+    let mut mismatched_pixels = 0;
+    let mut diff = Vec::with_capacity(actual.data.len());
+    for (expected, actual) in expected.data.chunks_exact(4).zip(actual.data.chunks_exact(4)) {
+        let matches = expected.iter().zip(actual).all(|(e, a)| e.abs_diff(*a) <= tolerance);
+        if matches {
+            let luma = ((actual[0] as u32+actual[1] as u32+actual[2] as u32)/3/4) as u8;
+            diff.extend_from_slice(&[luma, luma, luma, 255]);
+        } else {
+            mismatched_pixels += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        }
+    }
+
+    Ok(Comparison {
+        mismatched_pixels,
+        diff: RgbaImage {
+            width: actual.width,
+            height: actual.height,
+            data: diff,
+        },
+    })
+}
+
+pub fn load_png<P: AsRef<Path>>(path: P) -> std::result::Result<RgbaImage, GoldenError> {
+    Ok(RgbaImage::read_png(BufReader::new(File::open(path)?))?)
+}
+
+/// Clears the renderer's offscreen target, lets `draw` encode into it and reads the result back. Fails with
+/// [`Error::NotHeadless`] for renderers without an offscreen target.
+pub async fn render<F>(renderer: &Renderer, clear: wgpu::Color, draw: F) -> Result<RgbaImage>
+where
+    F: FnOnce(&mut wgpu::CommandEncoder, RenderTarget),
+{
+    let texture = renderer.offscreen_texture().ok_or(Error::NotHeadless)?;
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let target = renderer.render_target(&view);
+    let mut encoder = renderer.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Golden Encoder"),
+    });
+    encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+        label: Some("Golden Clear Pass"),
+        color_attachments: &[
+            Some(target.color_attachment(wgpu::LoadOp::Clear(clear)))
+        ],
+        depth_stencil_attachment: None,
+    });
+    draw(&mut encoder, target);
+    renderer.queue().submit(std::iter::once(encoder.finish()));
+    renderer.read_texture(texture).await
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    Io(std::io::Error),
+    Decoding(png::DecodingError),
+    Encoding(png::EncodingError),
+    MissingReference {
+        name: String,
+        path: PathBuf,
+    },
+    SizeMismatch {
+        name: String,
+        expected: (u32, u32),
+        actual: (u32, u32),
+    },
+    Mismatch {
+        name: String,
+        mismatched_pixels: usize,
+        diff_path: PathBuf,
+    },
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenError::Io(e) => write!(f, "golden image I/O failed: {}", e),
+            GoldenError::Decoding(e) => write!(f, "could not decode golden reference: {}", e),
+            GoldenError::Encoding(e) => write!(f, "could not encode golden image: {}", e),
+            GoldenError::MissingReference { name, path } => write!(
+                f,
+                "'{}' has no reference at {}, set {} to record it",
+                name, path.display(), BLESS_ENV
+            ),
+            GoldenError::SizeMismatch { name, expected, actual } => write!(
+                f,
+                "'{}' rendered at {}x{} but the reference is {}x{}",
+                name, actual.0, actual.1, expected.0, expected.1
+            ),
+            GoldenError::Mismatch { name, mismatched_pixels, diff_path } => write!(
+                f,
+                "'{}' differs from its reference in {} pixels, see {}",
+                name, mismatched_pixels, diff_path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {}
+
+impl From<std::io::Error> for GoldenError {
+    fn from(e: std::io::Error) -> Self { GoldenError::Io(e) }
+}
+
+impl From<png::DecodingError> for GoldenError {
+    fn from(e: png::DecodingError) -> Self { GoldenError::Decoding(e) }
+}
+
+impl From<png::EncodingError> for GoldenError {
+    fn from(e: png::EncodingError) -> Self { GoldenError::Encoding(e) }
+}
@@ -0,0 +1,414 @@
+use std::ops::Range;
+
+use wgpu::BufferAddress;
+
+use crate::{
+    blend::BlendMode,
+    color::Color,
+    instance_buffer::InstanceBuffer,
+    mask,
+    readback::RgbaImage,
+    rect::{FillAspect, SizeAndCenter},
+    shader,
+    target::RenderTarget,
+};
+
+#[cfg(feature = "decode")]
+use crate::error::{Error, Result};
+
+/// An RGBA texture for drawing with an [`ImageRenderer`] or a
+/// [`NinePatchRenderer`](crate::nine_patch::NinePatchRenderer).
+pub struct ImageTexture {
+    texture: wgpu::Texture,
+    bind_group: wgpu::BindGroup,
+    width: u32,
+    height: u32,
+}
+
+impl ImageTexture {
+    /// Creates an empty texture sampled with `filter`, to be filled with [`ImageTexture::write`].
+    pub fn new(device: &wgpu::Device, width: u32, height: u32, filter: wgpu::FilterMode) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Image Texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("Image Sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: filter,
+            min_filter: filter,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Image Texture Bind Group"),
+            layout: &texture_bind_group_layout(device),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&sampler),
+                }
+            ],
+        });
+
+        Self {
+            texture,
+            bind_group,
+            width,
+            height,
+        }
+    }
+
+    /// Uploads `image` to a new texture sampled with `filter`.
+    pub fn from_image(device: &wgpu::Device, queue: &wgpu::Queue, image: &RgbaImage, filter: wgpu::FilterMode) -> Self {
+        let texture = Self::new(device, image.width, image.height, filter);
+        texture.write(queue, 0, 0, image);
+        texture
+    }
+
+    pub fn texture(&self) -> &wgpu::Texture { &self.texture }
+    pub fn width(&self) -> u32 { self.width }
+    pub fn height(&self) -> u32 { self.height }
+    pub fn size(&self) -> [u32; 2] { [self.width, self.height] }
+    pub(crate) fn bind_group(&self) -> &wgpu::BindGroup { &self.bind_group }
+
+    /// Replaces the pixels in the region of the texture starting at `x`, `y` with `image`.
+    pub fn write(&self, queue: &wgpu::Queue, x: u32, y: u32, image: &RgbaImage) {
+        queue.write_texture(
+            wgpu::ImageCopyTexture {
+                texture: &self.texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d { x, y, z: 0 },
+                aspect: wgpu::TextureAspect::All,
+            },
+            &image.data,
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: std::num::NonZeroU32::new(image.width*4),
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: image.width,
+                height: image.height,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+/// The layout of [`ImageTexture`] bind groups. wgpu treats identical layouts as one, so every textured pipeline
+/// creating its own from this can bind any image texture.
+pub(crate) fn texture_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Image Texture Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                    multisampled: false,
+                },
+                count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1,
+                visibility: wgpu::ShaderStages::FRAGMENT,
+                ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                count: None,
+            }
+        ]
+    })
+}
+
+/// Draws textured quads. Each draw uses one [`ImageTexture`], so images sharing a texture share a draw.
+///
+/// Pixel data is sampled as is: sRGB encoded like every other colour, and premultiplied only when the renderer's
+/// blend mode expects premultiplied input.
+pub struct ImageRenderer {
+    image_buffer: InstanceBuffer<ImageRaw>,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl ImageRenderer {
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Image Shader", include_str!("image.wgsl"), texture_format, blend_mode);
+
+        let image_buffer = InstanceBuffer::new(device, "Image Buffer");
+
+        let image_layout = wgpu::VertexBufferLayout {
+            array_stride: IMAGE_RAW_SIZE,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4
+                }
+            ]
+        };
+
+        let texture_bind_group_layout = texture_bind_group_layout(device);
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Image Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Image Pipeline Layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout
+                ],
+                push_constant_ranges: &[],
+            })),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[
+                    image_layout
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(blend_mode.blend_state()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })]
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None
+        });
+
+        Self {
+            image_buffer,
+            pipeline,
+        }
+    }
+
+    pub fn set_image_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[ImageRaw]) {
+        self.image_buffer.set(device, queue, data);
+    }
+
+    pub fn image_buffer(&self) -> &InstanceBuffer<ImageRaw> {
+        &self.image_buffer
+    }
+
+    pub fn image_buffer_mut(&mut self) -> &mut InstanceBuffer<ImageRaw> {
+        &mut self.image_buffer
+    }
+
+    /// Draws every image in the buffer from `texture`.
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>, texture: &ImageTexture) {
+        self.render_range(encoder, target, loadop, texture, 0..self.image_buffer.len());
+    }
+
+    pub fn render_range<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>, texture: &ImageTexture, instances: Range<u32>) {
+        let target = target.into();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Image Render Pass"),
+            color_attachments: &[
+                Some(target.color_attachment(loadop))
+            ],
+            depth_stencil_attachment: None,
+        });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, texture, instances);
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, texture: &'a ImageTexture, instances: Range<u32>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &texture.bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.image_buffer.buffer().slice(..));
+        render_pass.draw(0..6, instances);
+    }
+}
+
+/// A region of a texture, in texture coordinates from (0, 0) at the top left to (1, 1) at the bottom right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UvRect {
+    pub min: [f32; 2],
+    pub max: [f32; 2],
+}
+
+impl UvRect {
+    pub const FULL: UvRect = UvRect {
+        min: [0.0, 0.0],
+        max: [1.0, 1.0],
+    };
+
+    /// The region covering the given pixels of a texture of size `texture_size`.
+    pub fn from_pixels(x: u32, y: u32, width: u32, height: u32, texture_size: [u32; 2]) -> Self {
+        let (texture_width, texture_height) = (texture_size[0] as f32, texture_size[1] as f32);
+        Self {
+            min: [x as f32/texture_width, y as f32/texture_height],
+            max: [(x+width) as f32/texture_width, (y+height) as f32/texture_height],
+        }
+    }
+}
+
+/// How an image is fitted into its sizing when their aspect ratios differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageFit {
+    /// Stretched to fill the sizing exactly.
+    #[default]
+    Stretch,
+    /// As large as fits inside the sizing, centred and letterboxed.
+    Contain,
+    /// Covering the whole sizing, centred and cropped.
+    Cover,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageDescriptor {
+    pub sizing: SizeAndCenter,
+    pub uv: UvRect,
+    /// Size in pixels of the texture `uv` refers to, which together with `uv` gives the aspect ratio to fit.
+    pub texture_size: [u32; 2],
+    pub fit: ImageFit,
+    /// Multiplies every texel; white leaves the image unchanged.
+    pub tint: Color,
+    pub resx: f32,
+    pub resy: f32,
+}
+
+const IMAGE_RAW_SIZE: BufferAddress = std::mem::size_of::<ImageRaw>() as BufferAddress;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ImageRaw {
+    pub scale: [f32; 2],
+    pub translation: [f32; 2],
+    /// Top left and bottom right texture coordinates.
+    pub uv: [f32; 4],
+    pub tint: [f32; 4],
+}
+
+impl From<ImageDescriptor> for ImageRaw {
+    fn from(descriptor: ImageDescriptor) -> Self {
+        let UvRect { min, max } = descriptor.uv;
+        let source_width = (max[0]-min[0])*descriptor.texture_size[0] as f32;
+        let source_height = (max[1]-min[1])*descriptor.texture_size[1] as f32;
+        let aspect = source_width/source_height;
+
+        let (sizing, uv) = match descriptor.fit {
+            ImageFit::Stretch => (descriptor.sizing, descriptor.uv),
+            ImageFit::Contain => (FillAspect {
+                placement_area: descriptor.sizing,
+                centerx: 0.0,
+                centery: 0.0,
+                resx: descriptor.resx,
+                resy: descriptor.resy,
+                aspect,
+            }.into(), descriptor.uv),
+            ImageFit::Cover => {
+                let area_aspect = (descriptor.sizing.sx*descriptor.resx)/(descriptor.sizing.sy*descriptor.resy);
+                // Shrink the source region about its centre to the sizing's aspect ratio.
+                let (keep_u, keep_v) = if area_aspect > aspect {
+                    (1.0, aspect/area_aspect)
+                } else {
+                    (area_aspect/aspect, 1.0)
+                };
+                let center = [(min[0]+max[0])/2.0, (min[1]+max[1])/2.0];
+                let half = [(max[0]-min[0])/2.0*keep_u, (max[1]-min[1])/2.0*keep_v];
+                (descriptor.sizing, UvRect {
+                    min: [center[0]-half[0], center[1]-half[1]],
+                    max: [center[0]+half[0], center[1]+half[1]],
+                })
+            },
+        };
+
+        Self {
+            scale: [sizing.sx, sizing.sy],
+            translation: [sizing.cx, sizing.cy],
+            uv: [uv.min[0], uv.min[1], uv.max[0], uv.max[1]],
+            tint: descriptor.tint.into(),
+        }
+    }
+}
+
+/// Decodes a PNG or JPEG, told apart by their signatures, into 8-bit RGBA.
+#[cfg(feature = "decode")]
+pub fn decode(bytes: &[u8]) -> Result<RgbaImage> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        return RgbaImage::read_png(bytes).map_err(|e| Error::ImageDecode(e.to_string()));
+    }
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        return decode_jpeg(bytes);
+    }
+    Err(Error::ImageDecode("not a PNG or JPEG".to_string()))
+}
+
+#[cfg(feature = "decode")]
+fn decode_jpeg(bytes: &[u8]) -> Result<RgbaImage> {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let pixels = decoder.decode().map_err(|e| Error::ImageDecode(e.to_string()))?;
+    let info = decoder.info().ok_or_else(|| Error::ImageDecode("missing JPEG header".to_string()))?;
+
+    let data = match info.pixel_format {
+        jpeg_decoder::PixelFormat::RGB24 => pixels.chunks_exact(3).flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect(),
+        jpeg_decoder::PixelFormat::L8 => pixels.iter().flat_map(|l| [*l, *l, *l, 255]).collect(),
+        pixel_format => return Err(Error::ImageDecode(format!("unsupported JPEG pixel format {:?}", pixel_format))),
+    };
+
+    Ok(RgbaImage {
+        width: info.width as u32,
+        height: info.height as u32,
+        data,
+    })
+}
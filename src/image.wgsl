@@ -0,0 +1,52 @@
+@group(0) @binding(0)
+var image_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var image_sampler: sampler;
+
+struct Instance {
+    @location(0) scale: vec2<f32>,
+    @location(1) translation: vec2<f32>,
+    // Top left and bottom right texture coordinates.
+    @location(2) uv: vec4<f32>,
+    @location(3) tint: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+};
+
+var<private> full: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, 1.0),
+);
+
+// Vertex shader
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: Instance,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = full[vertex_index];
+    out.position = vec4<f32>(corner*instance.scale+instance.translation, 0.0, 1.0);
+    // Texture coordinates run y down.
+    out.uv = mix(instance.uv.xy, instance.uv.zw, corner*vec2<f32>(0.5, -0.5)+0.5);
+    out.tint = instance.tint;
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn frag_main(
+    in: VertexOutput,
+) -> @location(0) vec4<f32> {
+    return output_color(textureSample(image_texture, image_sampler, in.uv)*in.tint);
+}
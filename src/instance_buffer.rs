@@ -0,0 +1,252 @@
+use std::ops::Range;
+
+/// Capacity of a freshly created buffer, in instances.
+const MIN_CAPACITY: u64 = 16;
+
+/// Refers to one instance inserted into an [`InstanceBuffer`]. Stays valid until the instance is removed or the
+/// buffer is [`set`](InstanceBuffer::set) or [`clear`](InstanceBuffer::clear)ed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstanceHandle {
+    index: u32,
+    generation: u32,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Slot {
+    /// Written by [`InstanceBuffer::set`], not addressable by any handle.
+    Unowned,
+    Owned(u32),
+    /// Zeroed so it draws nothing, and dropped at the next compaction.
+    Removed,
+}
+
+struct HandleEntry {
+    generation: u32,
+    slot: Option<u32>,
+}
+
+/// A vertex buffer of per-instance data that grows to fit whatever it is given.
+///
+/// Capacity doubles when exceeded, so filling it one frame at a time costs amortised constant reallocations.
+///
+/// Instances can either be replaced wholesale with [`set`](Self::set), or managed individually through handles with
+/// [`insert`](Self::insert), [`update`](Self::update) and [`remove`](Self::remove), in which case only the ranges
+/// that changed are uploaded by the next [`flush`](Self::flush). Instances are drawn in insertion order.
+pub struct InstanceBuffer<T: bytemuck::Pod> {
+    label: &'static str,
+    buffer: wgpu::Buffer,
+    capacity: u64,
+    /// Number of instances on the GPU as of the last upload.
+    len: u32,
+    instances: Vec<T>,
+    slots: Vec<Slot>,
+    handles: Vec<HandleEntry>,
+    free_handles: Vec<u32>,
+    removed_count: u32,
+    dirty: Vec<Range<u32>>,
+}
+
+impl<T: bytemuck::Pod> InstanceBuffer<T> {
+    pub fn new(device: &wgpu::Device, label: &'static str) -> Self {
+        Self::with_capacity(device, label, MIN_CAPACITY)
+    }
+
+    pub fn with_capacity(device: &wgpu::Device, label: &'static str, capacity: u64) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY);
+        Self {
+            label,
+            buffer: Self::create_buffer(device, label, capacity),
+            capacity,
+            len: 0,
+            instances: Vec::new(),
+            slots: Vec::new(),
+            handles: Vec::new(),
+            free_handles: Vec::new(),
+            removed_count: 0,
+            dirty: Vec::new(),
+        }
+    }
+
+    fn create_buffer(device: &wgpu::Device, label: &str, capacity: u64) -> wgpu::Buffer {
+        device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: Self::instance_size()*capacity,
+            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        })
+    }
+
+    fn instance_size() -> wgpu::BufferAddress {
+        std::mem::size_of::<T>() as wgpu::BufferAddress
+    }
+
+    /// Replaces the contents with `data` and uploads them immediately. Invalidates every handle.
+    pub fn set(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[T]) {
+        self.clear();
+        self.instances.extend_from_slice(data);
+        self.slots.resize(data.len(), Slot::Unowned);
+        self.reserve(device, data.len() as u64);
+        self.len = data.len() as u32;
+        queue.write_buffer(&self.buffer, 0, bytemuck::cast_slice::<T, u8>(data));
+        self.dirty.clear();
+    }
+
+    /// Removes every instance and invalidates every handle. Takes effect on the GPU at the next flush.
+    pub fn clear(&mut self) {
+        for entry in &mut self.handles {
+            if entry.slot.take().is_some() {
+                entry.generation = entry.generation.wrapping_add(1);
+            }
+        }
+        self.free_handles = (0..self.handles.len() as u32).rev().collect();
+        self.instances.clear();
+        self.slots.clear();
+        self.removed_count = 0;
+        self.dirty.clear();
+    }
+
+    /// Appends `instance`, to be drawn after every instance already in the buffer.
+    pub fn insert(&mut self, instance: T) -> InstanceHandle {
+        let slot = self.instances.len() as u32;
+        let index = match self.free_handles.pop() {
+            Some(index) => {
+                self.handles[index as usize].slot = Some(slot);
+                index
+            },
+            None => {
+                self.handles.push(HandleEntry {
+                    generation: 0,
+                    slot: Some(slot),
+                });
+                self.handles.len() as u32-1
+            },
+        };
+        self.instances.push(instance);
+        self.slots.push(Slot::Owned(index));
+        self.mark_dirty(slot);
+        InstanceHandle {
+            index,
+            generation: self.handles[index as usize].generation,
+        }
+    }
+
+    /// Returns `false` without changing anything if `handle` is no longer valid.
+    pub fn update(&mut self, handle: InstanceHandle, instance: T) -> bool {
+        let Some(slot) = self.slot(handle) else { return false };
+        self.instances[slot as usize] = instance;
+        self.mark_dirty(slot);
+        true
+    }
+
+    pub fn remove(&mut self, handle: InstanceHandle) -> Option<T> {
+        let slot = self.slot(handle)?;
+        let entry = &mut self.handles[handle.index as usize];
+        entry.slot = None;
+        entry.generation = entry.generation.wrapping_add(1);
+        self.free_handles.push(handle.index);
+
+        let instance = std::mem::replace(&mut self.instances[slot as usize], T::zeroed());
+        self.slots[slot as usize] = Slot::Removed;
+        self.removed_count += 1;
+        self.mark_dirty(slot);
+        Some(instance)
+    }
+
+    pub fn get(&self, handle: InstanceHandle) -> Option<&T> {
+        self.slot(handle).map(|slot| &self.instances[slot as usize])
+    }
+
+    fn slot(&self, handle: InstanceHandle) -> Option<u32> {
+        let entry = self.handles.get(handle.index as usize)?;
+        if entry.generation == handle.generation {
+            entry.slot
+        } else {
+            None
+        }
+    }
+
+    fn mark_dirty(&mut self, slot: u32) {
+        match self.dirty.last_mut() {
+            Some(range) if range.contains(&slot) => {},
+            Some(range) if range.end == slot => range.end += 1,
+            _ => self.dirty.push(slot..slot+1),
+        }
+    }
+
+    /// Uploads whatever changed since the last flush. Call once per frame before rendering.
+    ///
+    /// Removed instances are compacted away once they make up more than half of the buffer, which costs one full
+    /// upload.
+    pub fn flush(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        if self.removed_count > 0 && self.removed_count as usize*2 > self.instances.len() {
+            self.compact();
+        }
+
+        let len = self.instances.len() as u32;
+        self.reserve(device, len as u64);
+
+        self.dirty.sort_by_key(|range| range.start);
+        let mut dirty = std::mem::take(&mut self.dirty).into_iter().peekable();
+        while let Some(mut range) = dirty.next() {
+            while let Some(next) = dirty.next_if(|next| next.start <= range.end) {
+                range.end = range.end.max(next.end);
+            }
+            let range = range.start.min(len)..range.end.min(len);
+            if !range.is_empty() {
+                queue.write_buffer(
+                    &self.buffer,
+                    range.start as wgpu::BufferAddress*Self::instance_size(),
+                    bytemuck::cast_slice::<T, u8>(&self.instances[range.start as usize..range.end as usize]),
+                );
+            }
+        }
+        self.len = len;
+    }
+
+    fn compact(&mut self) {
+        let mut kept = 0;
+        for slot in 0..self.slots.len() {
+            match self.slots[slot] {
+                Slot::Removed => continue,
+                Slot::Owned(index) => self.handles[index as usize].slot = Some(kept as u32),
+                Slot::Unowned => {},
+            }
+            self.instances[kept] = self.instances[slot];
+            self.slots[kept] = self.slots[slot];
+            kept += 1;
+        }
+        self.instances.truncate(kept);
+        self.slots.truncate(kept);
+        self.removed_count = 0;
+        self.dirty.clear();
+        self.dirty.push(0..kept as u32);
+    }
+
+    /// Makes room for at least `capacity` instances. If that reallocates, everything is uploaded again at the next
+    /// flush.
+    pub fn reserve(&mut self, device: &wgpu::Device, capacity: u64) {
+        if capacity > self.capacity {
+            self.capacity = capacity.next_power_of_two();
+            self.buffer = Self::create_buffer(device, self.label, self.capacity);
+            self.dirty.clear();
+            self.dirty.push(0..self.instances.len() as u32);
+        }
+    }
+
+    /// Number of instances drawn as of the last upload, including removed ones that have not been compacted yet.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> u64 {
+        self.capacity
+    }
+
+    pub fn buffer(&self) -> &wgpu::Buffer {
+        &self.buffer
+    }
+}
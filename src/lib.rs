@@ -1,11 +1,32 @@
+mod error;
+pub use error::{Error, Result};
+
 pub mod renderer;
+pub mod target;
+pub mod clip;
+pub mod mask;
+pub mod color;
+pub mod blend;
+pub mod stroke;
+pub mod fill;
+mod shader;
+pub mod readback;
+pub mod golden;
+pub mod instance_buffer;
 pub mod rect;
 mod typed_uniform;
 pub mod resolution_buffer;
 pub mod ellipse;
+pub mod shadow;
+pub mod image;
+pub mod atlas;
+pub mod nine_patch;
 pub mod blockfont;
 pub mod line;
+pub mod polyline;
+pub mod path;
 pub mod ttf;
 pub mod ttf_outline;
 pub mod text;
-pub mod freeform_2dcapsule;
\ No newline at end of file
+pub mod freeform_2dcapsule;
+pub mod scene;
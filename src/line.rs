@@ -1,23 +1,35 @@
-use wgpu::{BufferAddress, include_wgsl};
+use std::ops::Range;
 
-use crate::rect::{SizeAndCenter, FillAspect};
+use wgpu::BufferAddress;
+
+use crate::{
+    blend::BlendMode,
+    instance_buffer::InstanceBuffer,
+    mask,
+    rect::{SizeAndCenter, FillAspect},
+    shader,
+    target::RenderTarget,
+};
 
 pub struct LineRenderer {
-    line_buffer: wgpu::Buffer,
-    line_count: u32,
+    line_buffer: InstanceBuffer<LineRaw>,
     pipeline: wgpu::RenderPipeline,
 }
 
 impl LineRenderer {
-    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, max_line_count: u64) -> Self {
-        let shader = device.create_shader_module(include_wgsl!("line.wgsl"));
-
-        let line_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Line Buffer"),
-            size: LINE_RAW_SIZE*max_line_count,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Line Shader", include_str!("line.wgsl"), texture_format, blend_mode);
+
+        let line_buffer = InstanceBuffer::new(device, "Line Buffer");
 
         let line_layout = wgpu::VertexBufferLayout {
             array_stride: LINE_RAW_SIZE,
@@ -55,7 +67,7 @@ impl LineRenderer {
                 entry_point: "frag_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: texture_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    blend: Some(blend_mode.blend_state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })]
             }),
@@ -68,9 +80,9 @@ impl LineRenderer {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -79,34 +91,40 @@ impl LineRenderer {
 
         Self {
             line_buffer,
-            line_count: 0,
             pipeline,
         }
     }
 
-    pub fn set_line_buffer(&mut self, queue: &wgpu::Queue, data: &[LineRaw]) {
-        self.line_count = data.len() as u32;
-        queue.write_buffer(&self.line_buffer, 0, bytemuck::cast_slice::<LineRaw, u8>(data));
+    pub fn set_line_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[LineRaw]) {
+        self.line_buffer.set(device, queue, data);
     }
 
-    pub fn render_all(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, loadop: wgpu::LoadOp<wgpu::Color>) {
+    pub fn line_buffer(&self) -> &InstanceBuffer<LineRaw> {
+        &self.line_buffer
+    }
+
+    pub fn line_buffer_mut(&mut self) -> &mut InstanceBuffer<LineRaw> {
+        &mut self.line_buffer
+    }
+
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        let target = target.into();
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Line Render Pass"),
             color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: loadop,
-                        store: true,
-                    }
-                })
+                Some(target.color_attachment(loadop))
             ],
             depth_stencil_attachment: None,
         });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, 0..self.line_buffer.len());
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_vertex_buffer(0, self.line_buffer.slice(..));
-        render_pass.draw(0..2, 0..self.line_count);
+        render_pass.set_vertex_buffer(0, self.line_buffer.buffer().slice(..));
+        render_pass.draw(0..2, instances);
     }
 }
 
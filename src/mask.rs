@@ -0,0 +1,411 @@
+use std::ops::Range;
+
+use wgpu::{BufferAddress, include_wgsl};
+
+use crate::{
+    instance_buffer::InstanceBuffer,
+    line::{LINE_RAW_SIZE, LineRaw},
+    rect::{CornerRadii, SizeAndCenter},
+    resolution_buffer::ResolutionBuffer,
+    shader,
+    target::RenderTarget,
+};
+
+pub(crate) const STENCIL_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
+/// Stencil bit glyph outlines are filled into before they join the mask. The bits below it count mask depth.
+const OUTLINE_BIT: u32 = 0x80;
+
+pub const MAX_MASK_DEPTH: u32 = OUTLINE_BIT-1;
+
+pub(crate) fn create_stencil_texture(device: &wgpu::Device, label: &str, resx: u32, resy: u32, sample_count: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some(label),
+        size: wgpu::Extent3d {
+            width: resx,
+            height: resy,
+            ..Default::default()
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: wgpu::TextureDimension::D2,
+        format: STENCIL_FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+    })
+}
+
+/// Depth-stencil state of the pipelines renderers create with `new_masked`: drawing passes only where the stencil
+/// equals the reference, which [`MaskRenderer::begin_masked_pass`] sets to the mask depth.
+pub(crate) fn masked_depth_stencil_state() -> wgpu::DepthStencilState {
+    wgpu::DepthStencilState {
+        format: STENCIL_FORMAT,
+        depth_write_enabled: false,
+        depth_compare: wgpu::CompareFunction::Always,
+        stencil: stencil_state(wgpu::CompareFunction::Equal, wgpu::StencilOperation::Keep, 0),
+        bias: wgpu::DepthBiasState::default()
+    }
+}
+
+/// Limits drawing to arbitrary shapes using the stencil buffer, generalising how [`TextRenderer`](crate::text::TextRenderer)
+/// fills glyphs.
+///
+/// Each [`push`](Self::push) draws a mask made of shapes from the shape buffer and glyph outlines from the outline
+/// buffer, inside whatever masks are already pushed, by incrementing the stencil where they cover. Renderers created
+/// with `new_masked` then draw into a [`begin_masked_pass`](Self::begin_masked_pass) only where every pushed mask
+/// covers, until [`pop`](Self::pop) decrements the stencil back. Mask edges are not anti-aliased beyond what
+/// multisampling gives.
+pub struct MaskRenderer {
+    shape_buffer: InstanceBuffer<MaskShapeRaw>,
+    outline_buffer: InstanceBuffer<LineRaw>,
+    shape_pipeline: wgpu::RenderPipeline,
+    outline_pipeline: wgpu::RenderPipeline,
+    resolve_outline_pipeline: wgpu::RenderPipeline,
+    clear_outline_pipeline: wgpu::RenderPipeline,
+    pop_pipeline: wgpu::RenderPipeline,
+    resolution_bind_group: wgpu::BindGroup,
+    stencil_texture: wgpu::Texture,
+    stencil_texture_view: wgpu::TextureView,
+    sample_count: u32,
+    depth: u32,
+}
+
+impl MaskRenderer {
+    /// `sample_count` must match the targets the masked passes draw into.
+    pub fn new(device: &wgpu::Device, sample_count: u32, resolution_buffer: &ResolutionBuffer, resx: u32, resy: u32) -> Self {
+        let shader = shader::create_stencil_shader_module(device, "Mask Shader", include_str!("mask.wgsl"));
+        let outline_shader = device.create_shader_module(include_wgsl!("text.wgsl"));
+
+        let shape_buffer = InstanceBuffer::new(device, "Mask Shape Buffer");
+        let outline_buffer = InstanceBuffer::new(device, "Mask Outline Buffer");
+
+        let shape_layout = wgpu::VertexBufferLayout {
+            array_stride: MASK_SHAPE_RAW_SIZE,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Uint32
+                }
+            ]
+        };
+
+        let outline_layout = wgpu::VertexBufferLayout {
+            array_stride: LINE_RAW_SIZE,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+            ]
+        };
+
+        let resolution_bind_group_layout = ResolutionBuffer::bind_group_layout(device);
+        let resolution_bind_group = resolution_buffer.bind_group(device, &resolution_bind_group_layout);
+
+        let shape_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mask Shape Pipeline Layout"),
+            bind_group_layouts: &[
+                &resolution_bind_group_layout
+            ],
+            push_constant_ranges: &[],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("Mask Pipeline Layout"),
+            bind_group_layouts: &[],
+            push_constant_ranges: &[],
+        });
+
+        let fullscreen = wgpu::VertexState {
+            module: &shader,
+            entry_point: "fullscreen_main",
+            buffers: &[],
+        };
+
+        // Shapes and resolved outlines raise the stencil from the current depth to the next.
+        let shape_pipeline = create_stencil_pipeline(device, "Mask Shape Pipeline", &shape_pipeline_layout, sample_count, wgpu::VertexState {
+            module: &shader,
+            entry_point: "vert_main",
+            buffers: &[
+                shape_layout
+            ],
+        }, Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: "frag_main",
+            targets: &[]
+        }), stencil_state(wgpu::CompareFunction::Equal, wgpu::StencilOperation::IncrementClamp, 0xff));
+        // Outlines are filled like text, by inverting a bit for every triangle covering a pixel.
+        let outline_pipeline = create_stencil_pipeline(device, "Mask Outline Pipeline", &pipeline_layout, sample_count, wgpu::VertexState {
+            module: &outline_shader,
+            entry_point: "vert_main",
+            buffers: &[
+                outline_layout
+            ],
+        }, None, stencil_state(wgpu::CompareFunction::Always, wgpu::StencilOperation::Invert, OUTLINE_BIT));
+        let resolve_outline_pipeline = create_stencil_pipeline(device, "Mask Resolve Outline Pipeline", &pipeline_layout, sample_count, fullscreen.clone(), None, stencil_state(wgpu::CompareFunction::Equal, wgpu::StencilOperation::IncrementClamp, 0xff));
+        let clear_outline_pipeline = create_stencil_pipeline(device, "Mask Clear Outline Pipeline", &pipeline_layout, sample_count, fullscreen.clone(), None, stencil_state(wgpu::CompareFunction::Always, wgpu::StencilOperation::Zero, OUTLINE_BIT));
+        let pop_pipeline = create_stencil_pipeline(device, "Mask Pop Pipeline", &pipeline_layout, sample_count, fullscreen, None, stencil_state(wgpu::CompareFunction::Equal, wgpu::StencilOperation::DecrementClamp, 0xff));
+
+        let stencil_texture = create_stencil_texture(device, "Mask Stencil", resx, resy, sample_count);
+        let stencil_texture_view = stencil_texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        Self {
+            shape_buffer,
+            outline_buffer,
+            shape_pipeline,
+            outline_pipeline,
+            resolve_outline_pipeline,
+            clear_outline_pipeline,
+            pop_pipeline,
+            resolution_bind_group,
+            stencil_texture,
+            stencil_texture_view,
+            sample_count,
+            depth: 0,
+        }
+    }
+
+    pub fn set_shape_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[MaskShapeRaw]) {
+        self.shape_buffer.set(device, queue, data);
+    }
+
+    pub fn shape_buffer(&self) -> &InstanceBuffer<MaskShapeRaw> {
+        &self.shape_buffer
+    }
+
+    pub fn shape_buffer_mut(&mut self) -> &mut InstanceBuffer<MaskShapeRaw> {
+        &mut self.shape_buffer
+    }
+
+    /// Glyph outline lines, as produced by `ttf_outline`.
+    pub fn set_outline_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[LineRaw]) {
+        self.outline_buffer.set(device, queue, data);
+    }
+
+    pub fn outline_buffer(&self) -> &InstanceBuffer<LineRaw> {
+        &self.outline_buffer
+    }
+
+    pub fn outline_buffer_mut(&mut self) -> &mut InstanceBuffer<LineRaw> {
+        &mut self.outline_buffer
+    }
+
+    /// Resizing drops every pushed mask.
+    pub fn on_resize(&mut self, device: &wgpu::Device, resx: u32, resy: u32) {
+        self.stencil_texture = create_stencil_texture(device, "Mask Stencil", resx, resy, self.sample_count);
+        self.stencil_texture_view = self.stencil_texture.create_view(&wgpu::TextureViewDescriptor::default());
+        self.depth = 0;
+    }
+
+    /// How many masks are pushed.
+    pub fn depth(&self) -> u32 {
+        self.depth
+    }
+
+    /// Pushes a mask covering the union of `shapes` from the shape buffer and the outline formed by `outlines` from the
+    /// outline buffer, limited to the masks already pushed.
+    ///
+    /// # Panics
+    ///
+    /// If [`MAX_MASK_DEPTH`] masks are already pushed.
+    pub fn push(&mut self, encoder: &mut wgpu::CommandEncoder, shapes: Range<u32>, outlines: Range<u32>) {
+        assert!(self.depth < MAX_MASK_DEPTH, "too many nested masks");
+        let mut stencil_pass = self.begin_stencil_pass(encoder, "Mask Push Pass");
+        if !shapes.is_empty() {
+            stencil_pass.set_pipeline(&self.shape_pipeline);
+            stencil_pass.set_bind_group(0, &self.resolution_bind_group, &[]);
+            stencil_pass.set_vertex_buffer(0, self.shape_buffer.buffer().slice(..));
+            stencil_pass.set_stencil_reference(self.depth);
+            stencil_pass.draw(0..6, shapes);
+        }
+        if !outlines.is_empty() {
+            stencil_pass.set_pipeline(&self.outline_pipeline);
+            stencil_pass.set_vertex_buffer(0, self.outline_buffer.buffer().slice(..));
+            stencil_pass.draw(0..3, outlines);
+            // Pixels inside the outline and the current mask, but not already raised by a shape, join the mask.
+            stencil_pass.set_pipeline(&self.resolve_outline_pipeline);
+            stencil_pass.set_stencil_reference(OUTLINE_BIT|self.depth);
+            stencil_pass.draw(0..3, 0..1);
+            stencil_pass.set_pipeline(&self.clear_outline_pipeline);
+            stencil_pass.draw(0..3, 0..1);
+        }
+        drop(stencil_pass);
+        self.depth += 1;
+    }
+
+    /// Removes the most recently pushed mask. Does nothing when no mask is pushed.
+    pub fn pop(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if self.depth == 0 {
+            return;
+        }
+        let mut stencil_pass = self.begin_stencil_pass(encoder, "Mask Pop Pass");
+        stencil_pass.set_pipeline(&self.pop_pipeline);
+        stencil_pass.set_stencil_reference(self.depth);
+        stencil_pass.draw(0..3, 0..1);
+        drop(stencil_pass);
+        self.depth -= 1;
+    }
+
+    /// Opens a render pass limited to the pushed masks.
+    ///
+    /// Only renderers created with `new_masked` can draw into it, by recording their `draw` into the returned pass,
+    /// and those renderers cannot draw into any other pass, as their pipelines expect this stencil attachment. Flush
+    /// their instance buffers before opening the pass, which borrows them until it ends.
+    pub fn begin_masked_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) -> wgpu::RenderPass<'a> {
+        let target = target.into();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Masked Render Pass"),
+            color_attachments: &[
+                Some(target.color_attachment(loadop))
+            ],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.stencil_texture_view,
+                depth_ops: None,
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                })
+            }),
+        });
+        if target.apply_scissor(&mut render_pass) {
+            render_pass.set_stencil_reference(self.depth);
+        } else {
+            // The caller draws into the pass regardless, so an empty scissor masks everything out instead. The stencil
+            // never holds a value above the deepest mask.
+            render_pass.set_stencil_reference(0xff);
+        }
+        render_pass
+    }
+
+    fn begin_stencil_pass<'a>(&'a self, encoder: &'a mut wgpu::CommandEncoder, label: &str) -> wgpu::RenderPass<'a> {
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some(label),
+            color_attachments: &[],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &self.stencil_texture_view,
+                depth_ops: None,
+                stencil_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                })
+            })
+        })
+    }
+}
+
+fn create_stencil_pipeline(
+    device: &wgpu::Device,
+    label: &str,
+    layout: &wgpu::PipelineLayout,
+    sample_count: u32,
+    vertex: wgpu::VertexState,
+    fragment: Option<wgpu::FragmentState>,
+    stencil: wgpu::StencilState,
+) -> wgpu::RenderPipeline {
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some(label),
+        layout: Some(layout),
+        vertex,
+        fragment,
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: wgpu::FrontFace::Ccw,
+            cull_mode: None,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: Some(wgpu::DepthStencilState {
+            format: STENCIL_FORMAT,
+            depth_write_enabled: false,
+            depth_compare: wgpu::CompareFunction::Always,
+            stencil,
+            bias: wgpu::DepthBiasState::default()
+        }),
+        multisample: wgpu::MultisampleState {
+            count: sample_count,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None
+    })
+}
+
+/// Stencil state that applies `pass_op` to the bits in `write_mask` wherever `compare` against the reference passes.
+fn stencil_state(compare: wgpu::CompareFunction, pass_op: wgpu::StencilOperation, write_mask: u32) -> wgpu::StencilState {
+    let face = wgpu::StencilFaceState {
+        compare,
+        fail_op: wgpu::StencilOperation::Keep,
+        depth_fail_op: wgpu::StencilOperation::Keep,
+        pass_op,
+    };
+    wgpu::StencilState {
+        front: face,
+        back: face,
+        read_mask: 0xff,
+        write_mask,
+    }
+}
+
+/// A shape making up part of a mask.
+#[derive(Debug, Clone, Copy)]
+pub enum MaskShape {
+    Rect {
+        sizing: SizeAndCenter,
+        corner_radii: CornerRadii,
+    },
+    Ellipse {
+        sizing: SizeAndCenter,
+    },
+}
+
+const MASK_SHAPE_RAW_SIZE: BufferAddress = std::mem::size_of::<MaskShapeRaw>() as BufferAddress;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct MaskShapeRaw {
+    pub scale: [f32; 2],
+    pub translation: [f32; 2],
+    /// Top left, top right, bottom right, bottom left, in pixels. Unused by ellipses.
+    pub corner_radii: [f32; 4],
+    /// 0 for a rect, 1 for an ellipse.
+    pub kind: u32,
+}
+
+impl From<MaskShape> for MaskShapeRaw {
+    fn from(shape: MaskShape) -> Self {
+        let (sizing, corner_radii, kind) = match shape {
+            MaskShape::Rect { sizing, corner_radii } => (sizing, corner_radii.into(), 0),
+            MaskShape::Ellipse { sizing } => (sizing, [0.0; 4], 1),
+        };
+        Self {
+            scale: [sizing.sx, sizing.sy],
+            translation: [sizing.cx, sizing.cy],
+            corner_radii,
+            kind,
+        }
+    }
+}
@@ -0,0 +1,80 @@
+struct Vec2f32 {
+    inner: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> res: Vec2f32;
+
+struct Instance {
+    @location(0) scale: vec2<f32>,
+    @location(1) translation: vec2<f32>,
+    @location(2) corner_radii: vec4<f32>,
+    // 0 for a rect, 1 for an ellipse.
+    @location(3) kind: u32,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    // Pixels from the shape's centre, y down.
+    @location(0) local: vec2<f32>,
+    @location(1) half_size: vec2<f32>,
+    @location(2) corner_radii: vec4<f32>,
+    @location(3) @interpolate(flat) kind: u32,
+};
+
+var<private> full: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, 1.0),
+);
+
+// Vertex shader
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: Instance,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let corner = full[vertex_index];
+    out.position = vec4<f32>(corner*instance.scale+instance.translation, 0.0, 1.0);
+    out.half_size = instance.scale*res.inner*0.5;
+    out.local = vec2<f32>(corner.x, -corner.y)*out.half_size;
+    out.corner_radii = instance.corner_radii;
+    out.kind = instance.kind;
+    return out;
+}
+
+// Covers the whole target, for the passes that rewrite the stencil everywhere.
+@vertex
+fn fullscreen_main(
+    @builtin(vertex_index) vertex_index: u32,
+) -> @builtin(position) vec4<f32> {
+    if (vertex_index == 0u) {
+        return vec4<f32>(1.0, -1.0, 0.0, 1.0);
+    } else if (vertex_index == 1u) {
+        return vec4<f32>(1.0, 3.0, 0.0, 1.0);
+    }
+    return vec4<f32>(-3.0, -1.0, 0.0, 1.0);
+}
+
+// Fragment shader
+
+// The stencil has no partial coverage, so a pixel is in the mask when its centre is inside the shape.
+@fragment
+fn frag_main(
+    in: VertexOutput,
+) {
+    var inside: bool;
+    if (in.kind == 1u) {
+        inside = length(in.local/max(in.half_size, vec2<f32>(0.0001))) <= 1.0;
+    } else {
+        inside = rounded_rect_distance(in.local, in.half_size, in.corner_radii) <= 0.0;
+    }
+    if (!inside) {
+        discard;
+    }
+}
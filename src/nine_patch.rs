@@ -0,0 +1,235 @@
+use std::ops::Range;
+
+use wgpu::BufferAddress;
+
+use crate::{
+    blend::BlendMode,
+    color::Color,
+    image::{self, ImageTexture, UvRect},
+    instance_buffer::InstanceBuffer,
+    mask,
+    rect::SizeAndCenter,
+    resolution_buffer::ResolutionBuffer,
+    shader,
+    target::RenderTarget,
+};
+
+/// Draws images sliced into a 3x3 grid whose corners keep their pixel size while the edges and centre stretch, as
+/// skinned panels and buttons need. Each instance is nine quads from one instanced draw.
+pub struct NinePatchRenderer {
+    nine_patch_buffer: InstanceBuffer<NinePatchRaw>,
+    pipeline: wgpu::RenderPipeline,
+    resolution_bind_group: wgpu::BindGroup,
+}
+
+impl NinePatchRenderer {
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Nine Patch Shader", include_str!("nine_patch.wgsl"), texture_format, blend_mode);
+
+        let nine_patch_buffer = InstanceBuffer::new(device, "Nine Patch Buffer");
+
+        let nine_patch_layout = wgpu::VertexBufferLayout {
+            array_stride: NINE_PATCH_RAW_SIZE,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 16]>() as BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4
+                }
+            ]
+        };
+
+        let texture_bind_group_layout = image::texture_bind_group_layout(device);
+
+        let resolution_bind_group_layout = ResolutionBuffer::bind_group_layout(device);
+        let resolution_bind_group = resolution_buffer.bind_group(device, &resolution_bind_group_layout);
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Nine Patch Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Nine Patch Pipeline Layout"),
+                bind_group_layouts: &[
+                    &texture_bind_group_layout,
+                    &resolution_bind_group_layout
+                ],
+                push_constant_ranges: &[],
+            })),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[
+                    nine_patch_layout
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(blend_mode.blend_state()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })]
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None
+        });
+
+        Self {
+            nine_patch_buffer,
+            pipeline,
+            resolution_bind_group,
+        }
+    }
+
+    pub fn set_nine_patch_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[NinePatchRaw]) {
+        self.nine_patch_buffer.set(device, queue, data);
+    }
+
+    pub fn nine_patch_buffer(&self) -> &InstanceBuffer<NinePatchRaw> {
+        &self.nine_patch_buffer
+    }
+
+    pub fn nine_patch_buffer_mut(&mut self) -> &mut InstanceBuffer<NinePatchRaw> {
+        &mut self.nine_patch_buffer
+    }
+
+    /// Draws every nine-patch in the buffer from `texture`.
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>, texture: &ImageTexture) {
+        self.render_range(encoder, target, loadop, texture, 0..self.nine_patch_buffer.len());
+    }
+
+    pub fn render_range<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>, texture: &ImageTexture, instances: Range<u32>) {
+        let target = target.into();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Nine Patch Render Pass"),
+            color_attachments: &[
+                Some(target.color_attachment(loadop))
+            ],
+            depth_stencil_attachment: None,
+        });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, texture, instances);
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, texture: &'a ImageTexture, instances: Range<u32>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, texture.bind_group(), &[]);
+        render_pass.set_bind_group(1, &self.resolution_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.nine_patch_buffer.buffer().slice(..));
+        // Nine quads of six vertices.
+        render_pass.draw(0..54, instances);
+    }
+}
+
+/// Widths in pixels of a nine-patch's borders, measured on the source image. They are drawn at the same size on screen,
+/// shrinking proportionally only when the sizing is too small to fit both sides.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct NinePatchInsets {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl NinePatchInsets {
+    pub const fn all(inset: f32) -> Self {
+        Self {
+            left: inset,
+            top: inset,
+            right: inset,
+            bottom: inset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct NinePatchDescriptor {
+    pub sizing: SizeAndCenter,
+    pub uv: UvRect,
+    /// Size in pixels of the texture `uv` refers to, to convert the insets into texture coordinates.
+    pub texture_size: [u32; 2],
+    pub insets: NinePatchInsets,
+    /// Multiplies every texel; white leaves the image unchanged.
+    pub tint: Color,
+}
+
+const NINE_PATCH_RAW_SIZE: BufferAddress = std::mem::size_of::<NinePatchRaw>() as BufferAddress;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct NinePatchRaw {
+    pub scale: [f32; 2],
+    pub translation: [f32; 2],
+    /// Top left and bottom right texture coordinates.
+    pub uv: [f32; 4],
+    /// Left, top, right and bottom insets in texture coordinates.
+    pub uv_insets: [f32; 4],
+    /// Left, top, right and bottom insets in pixels.
+    pub insets: [f32; 4],
+    pub tint: [f32; 4],
+}
+
+impl From<NinePatchDescriptor> for NinePatchRaw {
+    fn from(descriptor: NinePatchDescriptor) -> Self {
+        let NinePatchInsets { left, top, right, bottom } = descriptor.insets;
+        let (texture_width, texture_height) = (descriptor.texture_size[0] as f32, descriptor.texture_size[1] as f32);
+        Self {
+            scale: [descriptor.sizing.sx, descriptor.sizing.sy],
+            translation: [descriptor.sizing.cx, descriptor.sizing.cy],
+            uv: [descriptor.uv.min[0], descriptor.uv.min[1], descriptor.uv.max[0], descriptor.uv.max[1]],
+            uv_insets: [left/texture_width, top/texture_height, right/texture_width, bottom/texture_height],
+            insets: [left, top, right, bottom],
+            tint: descriptor.tint.into(),
+        }
+    }
+}
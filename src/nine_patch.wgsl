@@ -0,0 +1,80 @@
+@group(0) @binding(0)
+var image_texture: texture_2d<f32>;
+@group(0) @binding(1)
+var image_sampler: sampler;
+
+struct Vec2f32 {
+    inner: vec2<f32>,
+};
+
+@group(1) @binding(0)
+var<uniform> res: Vec2f32;
+
+struct Instance {
+    @location(0) scale: vec2<f32>,
+    @location(1) translation: vec2<f32>,
+    // Top left and bottom right texture coordinates.
+    @location(2) uv: vec4<f32>,
+    // Left, top, right and bottom, in texture coordinates and then in pixels.
+    @location(3) uv_insets: vec4<f32>,
+    @location(4) insets: vec4<f32>,
+    @location(5) tint: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) uv: vec2<f32>,
+    @location(1) tint: vec4<f32>,
+};
+
+var<private> full: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, 1.0),
+);
+
+// Vertex shader
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: Instance,
+) -> VertexOutput {
+    var out: VertexOutput;
+    // Cells go left to right, top to bottom.
+    let cell = vertex_index/6u;
+    let corner = full[vertex_index%6u];
+    let column = cell%3u+select(0u, 1u, corner.x > 0.0);
+    let row = cell/3u+select(1u, 0u, corner.y > 0.0);
+
+    let size = instance.scale*res.inner;
+    // Borders too wide for the sizing shrink together, keeping their proportions.
+    let fit = min(vec2<f32>(1.0), size/max(instance.insets.xy+instance.insets.zw, vec2<f32>(0.0001)));
+    let near = instance.insets.xy*fit;
+    let far = size-instance.insets.zw*fit;
+
+    // Distances in pixels from the top left, and the texture coordinates they sample.
+    var xs = array<f32, 4>(0.0, near.x, far.x, size.x);
+    var ys = array<f32, 4>(0.0, near.y, far.y, size.y);
+    var us = array<f32, 4>(instance.uv.x, instance.uv.x+instance.uv_insets.x, instance.uv.z-instance.uv_insets.z, instance.uv.z);
+    var vs = array<f32, 4>(instance.uv.y, instance.uv.y+instance.uv_insets.y, instance.uv.w-instance.uv_insets.w, instance.uv.w);
+
+    let top_left = instance.translation+vec2<f32>(-instance.scale.x, instance.scale.y);
+    let position = top_left+vec2<f32>(xs[column], -ys[row])*2.0/res.inner;
+    out.position = vec4<f32>(position, 0.0, 1.0);
+    out.uv = vec2<f32>(us[column], vs[row]);
+    out.tint = instance.tint;
+    return out;
+}
+
+// Fragment shader
+
+@fragment
+fn frag_main(
+    in: VertexOutput,
+) -> @location(0) vec4<f32> {
+    return output_color(textureSample(image_texture, image_sampler, in.uv)*in.tint);
+}
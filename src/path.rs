@@ -0,0 +1,288 @@
+use crate::{
+    polyline::{LineStyle, Polyline, PolylineSegmentRaw},
+    ttf::{CubeCurve, SquareCurve},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathCommand {
+    MoveTo([f32; 2]),
+    LineTo([f32; 2]),
+    QuadTo {
+        control: [f32; 2],
+        to: [f32; 2],
+    },
+    CubicTo {
+        control1: [f32; 2],
+        control2: [f32; 2],
+        to: [f32; 2],
+    },
+    Close,
+}
+
+/// Connected lines and curves, in the same coordinates as every other primitive, built up like an SVG path.
+///
+/// Drawing commands without a `move_to` before them start at their first point, and after a `close` they start again
+/// from where the closed subpath began.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    commands: Vec<PathCommand>,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts a new subpath at (`x`, `y`).
+    pub fn move_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.commands.push(PathCommand::MoveTo([x, y]));
+        self
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) -> &mut Self {
+        self.commands.push(PathCommand::LineTo([x, y]));
+        self
+    }
+
+    /// A quadratic Bézier curve to (`x`, `y`).
+    pub fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) -> &mut Self {
+        self.commands.push(PathCommand::QuadTo {
+            control: [cx, cy],
+            to: [x, y],
+        });
+        self
+    }
+
+    /// A cubic Bézier curve to (`x`, `y`).
+    pub fn cubic_to(&mut self, c1x: f32, c1y: f32, c2x: f32, c2y: f32, x: f32, y: f32) -> &mut Self {
+        self.commands.push(PathCommand::CubicTo {
+            control1: [c1x, c1y],
+            control2: [c2x, c2y],
+            to: [x, y],
+        });
+        self
+    }
+
+    /// Joins the current subpath back to its start.
+    pub fn close(&mut self) -> &mut Self {
+        self.commands.push(PathCommand::Close);
+        self
+    }
+
+    pub fn commands(&self) -> &[PathCommand] {
+        &self.commands
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// The subpaths as points joined by straight lines, splitting every curve into `curve_segments` lines. Curves are
+    /// always at least one line, so a `curve_segments` of 0 draws them straight.
+    pub fn flatten(&self, curve_segments: usize) -> Vec<Subpath> {
+        let curve_segments = curve_segments.max(1);
+        let mut subpaths = Vec::new();
+        let mut current = Subpath::default();
+        let mut start = [0.0; 2];
+
+        for command in &self.commands {
+            // Anything drawn after a close continues from where the closed subpath began.
+            if current.closed && *command != PathCommand::Close {
+                subpaths.push(std::mem::take(&mut current));
+                current.points.push(start);
+            }
+            match *command {
+                PathCommand::MoveTo(to) => {
+                    if current.points.len() > 1 {
+                        subpaths.push(std::mem::take(&mut current));
+                    }
+                    current.points.clear();
+                    current.points.push(to);
+                    start = to;
+                },
+                PathCommand::LineTo(to) => {
+                    if current.points.is_empty() {
+                        start = to;
+                    }
+                    current.points.push(to);
+                },
+                PathCommand::QuadTo { control, to } => {
+                    let from = *current.points.last().unwrap_or(&control);
+                    if current.points.is_empty() {
+                        current.points.push(from);
+                        start = from;
+                    }
+                    let curve = SquareCurve {
+                        p1x: from[0],
+                        p1y: from[1],
+                        c1x: control[0],
+                        c1y: control[1],
+                        p2x: to[0],
+                        p2y: to[1],
+                    };
+                    current.points.extend(curve.split_as_lines(curve_segments).iter().map(|line| [line.p2x, line.p2y]));
+                },
+                PathCommand::CubicTo { control1, control2, to } => {
+                    let from = *current.points.last().unwrap_or(&control1);
+                    if current.points.is_empty() {
+                        current.points.push(from);
+                        start = from;
+                    }
+                    let curve = CubeCurve {
+                        p1x: from[0],
+                        p1y: from[1],
+                        c1x: control1[0],
+                        c1y: control1[1],
+                        c2x: control2[0],
+                        c2y: control2[1],
+                        p2x: to[0],
+                        p2y: to[1],
+                    };
+                    current.points.extend(curve.split_as_lines(curve_segments).iter().map(|line| [line.p2x, line.p2y]));
+                },
+                PathCommand::Close => current.closed = current.points.len() > 1,
+            }
+        }
+        if current.points.len() > 1 {
+            subpaths.push(current);
+        }
+        subpaths
+    }
+
+    /// Segments for a [`PolylineRenderer`](crate::polyline::PolylineRenderer) drawing the path's outline.
+    ///
+    /// Curves are split into `curve_segments` lines each, and at least one, see [`Path::flatten`]. `resx` and `resy`
+    /// are the framebuffer size, to measure dashes in pixels.
+    pub fn stroke(&self, stroke: &PathStroke, curve_segments: usize, resx: f32, resy: f32) -> Vec<PolylineSegmentRaw> {
+        let mut segments = Vec::new();
+        for subpath in self.flatten(curve_segments) {
+            let polylines = match stroke.dashes() {
+                Some(pattern) => subpath.dash(&pattern, stroke.dash_offset, [resx/2.0, resy/2.0]),
+                None => vec![(subpath.points, subpath.closed)],
+            };
+            for (points, closed) in polylines {
+                segments.extend(Polyline {
+                    points,
+                    closed,
+                    style: stroke.style,
+                }.to_raw());
+            }
+        }
+        segments
+    }
+}
+
+/// Part of a [`Path`] flattened into straight lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Subpath {
+    pub points: Vec<[f32; 2]>,
+    /// Whether the last point joins back to the first.
+    pub closed: bool,
+}
+
+impl Subpath {
+    /// Splits the subpath into the open polylines the "on" parts of the dash `pattern` cover. Lengths are measured
+    /// after multiplying by `scale`.
+    fn dash(&self, pattern: &[f32], offset: f32, scale: [f32; 2]) -> Vec<(Vec<[f32; 2]>, bool)> {
+        let mut points = self.points.clone();
+        if self.closed {
+            points.push(points[0]);
+        }
+
+        // Find where in the pattern the subpath starts.
+        let mut index = 0;
+        let mut remaining = pattern[0];
+        let mut skip = offset.rem_euclid(pattern.iter().sum());
+        while skip > remaining {
+            skip -= remaining;
+            index = (index+1)%pattern.len();
+            remaining = pattern[index];
+        }
+        remaining -= skip;
+
+        let mut dashes = Vec::new();
+        let starts_on = index%2 == 0;
+        let mut current = if starts_on { vec![points[0]] } else { Vec::new() };
+        for pair in points.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            let length = ((b[0]-a[0])*scale[0]).hypot((b[1]-a[1])*scale[1]);
+            let mut travelled = 0.0;
+            while length-travelled > remaining {
+                travelled += remaining;
+                let t = travelled/length;
+                let point = [a[0]+(b[0]-a[0])*t, a[1]+(b[1]-a[1])*t];
+                current.push(point);
+                if index%2 == 0 {
+                    dashes.push(std::mem::take(&mut current));
+                }
+                index = (index+1)%pattern.len();
+                remaining = pattern[index];
+            }
+            remaining -= length-travelled;
+            if index%2 == 0 {
+                current.push(b);
+            }
+        }
+        let ends_on = index%2 == 0 && current.len() > 1;
+        if ends_on {
+            dashes.push(current);
+        }
+
+        if self.closed && starts_on && ends_on {
+            // The pattern is longer than the loop, which is drawn whole.
+            if dashes.len() == 1 {
+                dashes[0].pop();
+                return vec![(dashes.remove(0), true)];
+            }
+            // A closed subpath has no real start, so a dash running over it is one dash.
+            let first = dashes.remove(0);
+            dashes.last_mut().unwrap().extend(first.into_iter().skip(1));
+        }
+        dashes.into_iter().map(|dash| (dash, false)).collect()
+    }
+}
+
+/// How to stroke a [`Path`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathStroke {
+    pub style: LineStyle,
+    /// Alternating dash and gap lengths in pixels, repeated along each subpath. Empty for a solid line. An odd number
+    /// of lengths is repeated to make an even one, as in SVG.
+    pub dash_pattern: Vec<f32>,
+    /// How far into the dash pattern each subpath starts, in pixels.
+    pub dash_offset: f32,
+}
+
+impl PathStroke {
+    pub fn solid(style: LineStyle) -> Self {
+        Self {
+            style,
+            dash_pattern: Vec::new(),
+            dash_offset: 0.0,
+        }
+    }
+
+    pub fn dashed(style: LineStyle, dash_pattern: Vec<f32>) -> Self {
+        Self {
+            style,
+            dash_pattern,
+            dash_offset: 0.0,
+        }
+    }
+
+    /// The pattern with an even number of lengths, or `None` if it draws solid.
+    fn dashes(&self) -> Option<Vec<f32>> {
+        if self.dash_pattern.iter().sum::<f32>() <= 0.0 || self.dash_pattern.iter().any(|length| *length < 0.0) {
+            return None;
+        }
+        let mut pattern = self.dash_pattern.clone();
+        if pattern.len()%2 == 1 {
+            pattern.extend_from_within(..);
+        }
+        Some(pattern)
+    }
+}
@@ -0,0 +1,298 @@
+use std::ops::Range;
+
+use wgpu::BufferAddress;
+
+use crate::{
+    blend::BlendMode,
+    color::Color,
+    instance_buffer::InstanceBuffer,
+    mask,
+    resolution_buffer::ResolutionBuffer,
+    shader,
+    target::RenderTarget,
+};
+
+/// Draws lines and connected polylines of any width, anti-aliased, with caps at their ends and joins at their corners.
+///
+/// Every segment is one instance that also knows its neighbours, so it can draw the join at its end and leave out
+/// what the previous segment covers. Build the instances with [`Polyline::to_raw`].
+pub struct PolylineRenderer {
+    segment_buffer: InstanceBuffer<PolylineSegmentRaw>,
+    pipeline: wgpu::RenderPipeline,
+    resolution_bind_group: wgpu::BindGroup,
+}
+
+impl PolylineRenderer {
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Polyline Shader", include_str!("polyline.wgsl"), texture_format, blend_mode);
+
+        let segment_buffer = InstanceBuffer::new(device, "Polyline Segment Buffer");
+
+        let segment_layout = wgpu::VertexBufferLayout {
+            array_stride: POLYLINE_SEGMENT_RAW_SIZE,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 6]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4
+                }
+            ]
+        };
+
+        let resolution_bind_group_layout = ResolutionBuffer::bind_group_layout(device);
+        let resolution_bind_group = resolution_buffer.bind_group(device, &resolution_bind_group_layout);
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Polyline Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Polyline Pipeline Layout"),
+                bind_group_layouts: &[
+                    &resolution_bind_group_layout
+                ],
+                push_constant_ranges: &[],
+            })),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[
+                    segment_layout
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(blend_mode.blend_state()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })]
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleStrip,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: None,
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None
+        });
+
+        Self {
+            segment_buffer,
+            pipeline,
+            resolution_bind_group,
+        }
+    }
+
+    pub fn set_segment_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[PolylineSegmentRaw]) {
+        self.segment_buffer.set(device, queue, data);
+    }
+
+    pub fn segment_buffer(&self) -> &InstanceBuffer<PolylineSegmentRaw> {
+        &self.segment_buffer
+    }
+
+    pub fn segment_buffer_mut(&mut self) -> &mut InstanceBuffer<PolylineSegmentRaw> {
+        &mut self.segment_buffer
+    }
+
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        let target = target.into();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Polyline Render Pass"),
+            color_attachments: &[
+                Some(target.color_attachment(loadop))
+            ],
+            depth_stencil_attachment: None,
+        });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, 0..self.segment_buffer.len());
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.resolution_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.segment_buffer.buffer().slice(..));
+        render_pass.draw(0..4, instances);
+    }
+}
+
+/// How the open ends of a line are drawn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineCap {
+    /// Stops square at the end point.
+    #[default]
+    Butt,
+    /// A half circle around the end point.
+    Round,
+    /// Extends past the end point by half the width.
+    Square,
+}
+
+impl LineCap {
+    fn raw(self) -> f32 {
+        match self {
+            LineCap::Butt => 0.0,
+            LineCap::Round => 1.0,
+            LineCap::Square => 2.0,
+        }
+    }
+}
+
+/// How the outside of a polyline's corners is filled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineJoin {
+    /// Extends the outer edges until they meet, falling back to a bevel past the [`LineStyle::miter_limit`].
+    #[default]
+    Miter,
+    Round,
+    /// Cuts the corner off straight.
+    Bevel,
+}
+
+impl LineJoin {
+    fn raw(self) -> f32 {
+        match self {
+            LineJoin::Miter => 0.0,
+            LineJoin::Round => 1.0,
+            LineJoin::Bevel => 2.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    /// In pixels.
+    pub width: f32,
+    pub color: Color,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// The longest a miter may be, as a multiple of the width, as in SVG.
+    pub miter_limit: f32,
+}
+
+impl LineStyle {
+    pub const DEFAULT_MITER_LIMIT: f32 = 4.0;
+
+    pub fn new(width: f32, color: Color) -> Self {
+        Self {
+            width,
+            color,
+            cap: LineCap::default(),
+            join: LineJoin::default(),
+            miter_limit: Self::DEFAULT_MITER_LIMIT,
+        }
+    }
+}
+
+/// Points joined by straight segments, in the same coordinates as every other primitive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<[f32; 2]>,
+    /// Joins the last point back to the first, leaving no ends to cap.
+    pub closed: bool,
+    pub style: LineStyle,
+}
+
+impl Polyline {
+    /// A single segment.
+    pub fn line(p1: [f32; 2], p2: [f32; 2], style: LineStyle) -> Self {
+        Self {
+            points: vec![p1, p2],
+            closed: false,
+            style,
+        }
+    }
+
+    /// One instance per segment. Repeated points are skipped, and a lone point becomes a dot if the cap is not butt.
+    pub fn to_raw(&self) -> Vec<PolylineSegmentRaw> {
+        let mut points = self.points.clone();
+        points.dedup();
+        if self.closed && points.len() > 2 && points.first() == points.last() {
+            points.pop();
+        }
+
+        let style = [self.style.width, self.style.cap.raw(), self.style.join.raw(), self.style.miter_limit];
+        let color = self.style.color.into();
+        let segment = |p0, p1, p2, p3| PolylineSegmentRaw {
+            p0,
+            p1,
+            p2,
+            p3,
+            color,
+            style,
+        };
+
+        let count = points.len();
+        match count {
+            0 => Vec::new(),
+            1 => vec![segment(points[0], points[0], points[0], points[0])],
+            _ if self.closed && count > 2 => (0..count)
+                .map(|i| segment(points[(i+count-1)%count], points[i], points[(i+1)%count], points[(i+2)%count]))
+                .collect(),
+            _ => (0..count-1)
+                .map(|i| segment(points[i.saturating_sub(1)], points[i], points[i+1], points[(i+2).min(count-1)]))
+                .collect(),
+        }
+    }
+}
+
+const POLYLINE_SEGMENT_RAW_SIZE: BufferAddress = std::mem::size_of::<PolylineSegmentRaw>() as BufferAddress;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PolylineSegmentRaw {
+    /// The far end of the previous segment, or `p1` at the start of an open polyline.
+    pub p0: [f32; 2],
+    pub p1: [f32; 2],
+    pub p2: [f32; 2],
+    /// The far end of the next segment, or `p2` at the end of an open polyline.
+    pub p3: [f32; 2],
+    pub color: [f32; 4],
+    /// Width in pixels, cap, join and miter limit.
+    pub style: [f32; 4],
+}
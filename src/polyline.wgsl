@@ -0,0 +1,189 @@
+struct Vec2f32 {
+    inner: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> res: Vec2f32;
+
+// Caps and joins, see polyline.rs.
+let BUTT: f32 = 0.0;
+let ROUND: f32 = 1.0;
+let SQUARE: f32 = 2.0;
+let MITER: f32 = 0.0;
+
+struct Instance {
+    // The segment goes from p1 to p2. p0 and p3 are its neighbours' far ends, or repeat p1 and p2 where there are none.
+    @location(0) p0: vec2<f32>,
+    @location(1) p1: vec2<f32>,
+    @location(2) p2: vec2<f32>,
+    @location(3) p3: vec2<f32>,
+    @location(4) color: vec4<f32>,
+    // Width in pixels, cap, join and miter limit.
+    @location(5) style: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // Everything below is in framebuffer pixels.
+    @location(1) p0: vec2<f32>,
+    @location(2) p1: vec2<f32>,
+    @location(3) p2: vec2<f32>,
+    @location(4) p3: vec2<f32>,
+    @location(5) style: vec4<f32>,
+};
+
+fn to_pixels(p: vec2<f32>) -> vec2<f32> {
+    return (p*vec2<f32>(0.5, -0.5)+0.5)*res.inner;
+}
+
+fn from_pixels(p: vec2<f32>) -> vec2<f32> {
+    return (p/res.inner - 0.5)*vec2<f32>(2.0, -2.0);
+}
+
+fn direction(start: vec2<f32>, end: vec2<f32>) -> vec2<f32> {
+    if (all(start == end)) {
+        return vec2<f32>(1.0, 0.0);
+    }
+    return normalize(end-start);
+}
+
+// How far past the corner a join can reach.
+fn join_extent(half_width: f32, join: f32, miter_limit: f32) -> f32 {
+    if (join == MITER) {
+        return half_width*max(miter_limit, 1.0);
+    }
+    return half_width;
+}
+
+// Vertex shader
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: Instance,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let p1 = to_pixels(instance.p1);
+    let p2 = to_pixels(instance.p2);
+    let half_width = instance.style.x*0.5;
+
+    // Room for a cap at either end, the join at the end, and a pixel of margin for the anti-aliased edge.
+    var extent = half_width;
+    if (any(instance.p3 != instance.p2)) {
+        extent = max(extent, join_extent(half_width, instance.style.z, instance.style.w));
+    }
+    let along = direction(p1, p2)*(extent+1.0);
+    let across = vec2<f32>(-along.y, along.x);
+
+    var corner: vec2<f32>;
+    switch (vertex_index) {
+        case 0u: {
+            corner = p2+along+across;
+        }
+        case 1u: {
+            corner = p1-along+across;
+        }
+        case 2u: {
+            corner = p2+along-across;
+        }
+        default: {
+            corner = p1-along-across;
+        }
+    }
+    out.position = vec4<f32>(from_pixels(corner), 0.0, 1.0);
+    out.color = instance.color;
+    out.p0 = to_pixels(instance.p0);
+    out.p1 = p1;
+    out.p2 = p2;
+    out.p3 = to_pixels(instance.p3);
+    out.style = instance.style;
+    return out;
+}
+
+// Fragment shader
+
+// Signed distance in pixels from `p` to a segment drawn `half_width` either side of its centre line, with the given caps.
+fn segment_distance(p: vec2<f32>, start: vec2<f32>, end: vec2<f32>, half_width: f32, start_cap: f32, end_cap: f32) -> f32 {
+    let dir = direction(start, end);
+    let local = p-start;
+    let u = dot(local, dir);
+    let v = dot(local, vec2<f32>(-dir.y, dir.x));
+    let start_extension = select(0.0, half_width, start_cap == SQUARE);
+    let end_extension = select(0.0, half_width, end_cap == SQUARE);
+    var d = max(abs(v) - half_width, max(-start_extension - u, u - distance(start, end) - end_extension));
+    if (start_cap == ROUND) {
+        d = min(d, length(p-start) - half_width);
+    }
+    if (end_cap == ROUND) {
+        d = min(d, length(p-end) - half_width);
+    }
+    return d;
+}
+
+// Signed distance to the join filling the gap on the outside of a corner, `x` pixels from it, between a segment going
+// in direction `a` and the next going in direction `b`.
+fn join_distance(x: vec2<f32>, a: vec2<f32>, b: vec2<f32>, half_width: f32, join: f32, miter_limit: f32) -> f32 {
+    let bend = a-b;
+    // Straight on: the butt ends meet, and a round join hides the seam between them.
+    if (length(bend) < 0.0001) {
+        return length(x) - half_width;
+    }
+    let outward = normalize(bend);
+    var normal_a = vec2<f32>(-a.y, a.x);
+    if (dot(normal_a, outward) < 0.0) {
+        normal_a = -normal_a;
+    }
+    var normal_b = vec2<f32>(-b.y, b.x);
+    if (dot(normal_b, outward) < 0.0) {
+        normal_b = -normal_b;
+    }
+    let cos_half = dot(outward, normal_a);
+
+    // Between the two butt ends, overlapping each segment by a pixel so their anti-aliased edges do not show.
+    let wedge = max(-dot(x, a) - 1.0, dot(x, b) - 1.0);
+    var shape: f32;
+    if (join == ROUND) {
+        shape = length(x) - half_width;
+    } else if (join == MITER && cos_half*miter_limit >= 1.0) {
+        shape = max(max(dot(x, normal_a), dot(x, normal_b)) - half_width, -dot(x, outward) - half_width);
+    } else {
+        // Bevels, and miters too sharp for the limit.
+        shape = max(length(x) - half_width, dot(x, outward) - half_width*cos_half);
+    }
+    return max(wedge, shape);
+}
+
+@fragment
+fn frag_main(
+    in: VertexOutput,
+) -> @location(0) vec4<f32> {
+    let half_width = in.style.x*0.5;
+    let cap = in.style.y;
+    let join = in.style.z;
+    let miter_limit = in.style.w;
+    let has_previous = any(in.p0 != in.p1);
+    let has_next = any(in.p3 != in.p2);
+    let p = in.position.xy;
+
+    // Each segment draws the join at its end. Joined ends are butt ends hidden under the join.
+    var d = segment_distance(p, in.p1, in.p2, half_width, select(cap, BUTT, has_previous), select(cap, BUTT, has_next));
+    if (has_next) {
+        d = min(d, join_distance(p-in.p2, direction(in.p1, in.p2), direction(in.p2, in.p3), half_width, join, miter_limit));
+    }
+
+    var shape_coverage = coverage(d);
+    // Leave out what the previous segment and its join already cover, so translucent lines are not darker where they
+    // overlap.
+    if (has_previous) {
+        let previous = min(
+            segment_distance(p, in.p0, in.p1, half_width, BUTT, BUTT),
+            join_distance(p-in.p1, direction(in.p0, in.p1), direction(in.p1, in.p2), half_width, join, miter_limit)
+        );
+        shape_coverage = max(coverage(min(d, previous)) - coverage(previous), 0.0);
+    }
+    if (shape_coverage <= 0.0) {
+        discard;
+    }
+    return output_color(apply_coverage(in.color, shape_coverage));
+}
@@ -1,21 +1,38 @@
-use wgpu::{BufferAddress, include_wgsl};
+use std::ops::Range;
+
+use wgpu::BufferAddress;
+
+use crate::{
+    blend::BlendMode,
+    fill::{Fill, FillRaw},
+    instance_buffer::InstanceBuffer,
+    mask,
+    resolution_buffer::ResolutionBuffer,
+    shader,
+    stroke::Stroke,
+    target::RenderTarget,
+};
 
 pub struct RectRenderer {
-    rect_buffer: wgpu::Buffer,
-    rect_count: u32,
+    rect_buffer: InstanceBuffer<RectRaw>,
     pipeline: wgpu::RenderPipeline,
+    resolution_bind_group: wgpu::BindGroup,
 }
 
 impl RectRenderer {
-    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, max_rect_count: u64) -> Self {
-        let shader = device.create_shader_module(include_wgsl!("rect.wgsl"));
-
-        let rect_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Rect Buffer"),
-            size: RECT_RAW_SIZE*max_rect_count,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Rect Shader", include_str!("rect.wgsl"), texture_format, blend_mode);
+
+        let rect_buffer = InstanceBuffer::new(device, "Rect Buffer");
 
         let rect_layout = wgpu::VertexBufferLayout {
             array_stride: RECT_RAW_SIZE,
@@ -32,18 +49,63 @@ impl RectRenderer {
                     format: wgpu::VertexFormat::Float32x2
                 },
                 wgpu::VertexAttribute {
-                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress*2,
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
                     shader_location: 2,
                     format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 16]>() as BufferAddress,
+                    shader_location: 5,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 20]>() as BufferAddress,
+                    shader_location: 6,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 24]>() as BufferAddress,
+                    shader_location: 7,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 28]>() as BufferAddress,
+                    shader_location: 8,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 32]>() as BufferAddress,
+                    shader_location: 9,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 36]>() as BufferAddress,
+                    shader_location: 10,
+                    format: wgpu::VertexFormat::Float32x2
                 }
             ]
         };
 
+        let resolution_bind_group_layout = ResolutionBuffer::bind_group_layout(device);
+        let resolution_bind_group = resolution_buffer.bind_group(device, &resolution_bind_group_layout);
+
         let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Rect Pipeline"),
             layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
                 label: Some("Rect Pipeline Layout"),
-                bind_group_layouts: &[],
+                bind_group_layouts: &[
+                    &resolution_bind_group_layout
+                ],
                 push_constant_ranges: &[],
             })),
             vertex: wgpu::VertexState {
@@ -58,7 +120,7 @@ impl RectRenderer {
                 entry_point: "frag_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: texture_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    blend: Some(blend_mode.blend_state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })]
             }),
@@ -71,9 +133,9 @@ impl RectRenderer {
                 unclipped_depth: false,
                 conservative: false,
             },
-            depth_stencil: None,
+            depth_stencil,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -82,44 +144,80 @@ impl RectRenderer {
 
         Self {
             rect_buffer,
-            rect_count: 0,
             pipeline,
+            resolution_bind_group,
         }
     }
 
-    pub fn set_rect_buffer(&mut self, queue: &wgpu::Queue, data: &[RectRaw]) {
-        self.rect_count = data.len() as u32;
-        queue.write_buffer(&self.rect_buffer, 0, bytemuck::cast_slice::<RectRaw, u8>(data));
+    pub fn set_rect_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[RectRaw]) {
+        self.rect_buffer.set(device, queue, data);
+    }
+
+    pub fn rect_buffer(&self) -> &InstanceBuffer<RectRaw> {
+        &self.rect_buffer
     }
 
-    pub fn render_all(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, loadop: wgpu::LoadOp<wgpu::Color>) {
+    pub fn rect_buffer_mut(&mut self) -> &mut InstanceBuffer<RectRaw> {
+        &mut self.rect_buffer
+    }
+
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        let target = target.into();
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Menu Render Pass"),
             color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: loadop,
-                        store: true,
-                    }
-                })
+                Some(target.color_attachment(loadop))
             ],
             depth_stencil_attachment: None,
         });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, 0..self.rect_buffer.len());
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_vertex_buffer(0, self.rect_buffer.slice(..));
-        render_pass.draw(0..6, 0..self.rect_count);
+        render_pass.set_bind_group(0, &self.resolution_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.rect_buffer.buffer().slice(..));
+        render_pass.draw(0..6, instances);
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct RectDescriptor {
     pub sizing: SizeAndCenter,
-    pub r: f32,
-    pub g: f32,
-    pub b: f32,
-    pub a: f32,
+    pub fill: Fill,
+    pub corner_radii: CornerRadii,
+    pub stroke: Stroke,
+}
+
+/// Corner radii in pixels, so corners stay circular whatever the aspect ratio. Radii are clamped to half of the
+/// rect's shorter side.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CornerRadii {
+    pub top_left: f32,
+    pub top_right: f32,
+    pub bottom_right: f32,
+    pub bottom_left: f32,
+}
+
+impl CornerRadii {
+    pub const ZERO: CornerRadii = CornerRadii::all(0.0);
+
+    pub const fn all(radius: f32) -> Self {
+        Self {
+            top_left: radius,
+            top_right: radius,
+            bottom_right: radius,
+            bottom_left: radius,
+        }
+    }
+}
+
+impl From<CornerRadii> for [f32; 4] {
+    fn from(radii: CornerRadii) -> Self {
+        [radii.top_left, radii.top_right, radii.bottom_right, radii.bottom_left]
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -297,7 +395,12 @@ const RECT_RAW_SIZE: BufferAddress = std::mem::size_of::<RectRaw>() as BufferAdd
 pub struct RectRaw {
     pub scale: [f32; 2],
     pub translation: [f32; 2],
-    pub color: [f32; 4],
+    pub fill: FillRaw,
+    /// Top left, top right, bottom right, bottom left, in pixels.
+    pub corner_radii: [f32; 4],
+    pub stroke_color: [f32; 4],
+    /// Stroke width and how much of it lies outside the rect, in pixels.
+    pub stroke: [f32; 2],
 }
 
 impl From<RectDescriptor> for RectRaw {
@@ -305,7 +408,10 @@ impl From<RectDescriptor> for RectRaw {
         Self {
             scale: [descriptor.sizing.sx, descriptor.sizing.sy],
             translation: [descriptor.sizing.cx, descriptor.sizing.cy],
-            color: [descriptor.r, descriptor.g, descriptor.b, descriptor.a]
+            fill: descriptor.fill.into(),
+            corner_radii: descriptor.corner_radii.into(),
+            stroke_color: descriptor.stroke.color.into(),
+            stroke: descriptor.stroke.width_and_outset(),
         }
     }
 }
\ No newline at end of file
@@ -1,12 +1,39 @@
+struct Vec2f32 {
+    inner: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> res: Vec2f32;
+
 struct Instance {
     @location(0) scale: vec2<f32>,
     @location(1) translation: vec2<f32>,
-    @location(2) color: vec4<f32>,
+    // See fill.rs.
+    @location(2) fill_shape: vec4<f32>,
+    @location(3) fill_color0: vec4<f32>,
+    @location(4) fill_color1: vec4<f32>,
+    @location(5) fill_color2: vec4<f32>,
+    @location(6) fill_color3: vec4<f32>,
+    @location(7) fill_offsets: vec4<f32>,
+    @location(8) corner_radii: vec4<f32>,
+    @location(9) stroke_color: vec4<f32>,
+    // Width and how much of it lies outside the rect, both in pixels.
+    @location(10) stroke: vec2<f32>,
 };
 
 struct VertexOutput {
     @builtin(position) position: vec4<f32>,
-    @location(0) color: vec4<f32>,
+    @location(0) scale: vec2<f32>,
+    @location(1) translation: vec2<f32>,
+    @location(2) fill_shape: vec4<f32>,
+    @location(3) fill_color0: vec4<f32>,
+    @location(4) fill_color1: vec4<f32>,
+    @location(5) fill_color2: vec4<f32>,
+    @location(6) fill_color3: vec4<f32>,
+    @location(7) fill_offsets: vec4<f32>,
+    @location(8) corner_radii: vec4<f32>,
+    @location(9) stroke_color: vec4<f32>,
+    @location(10) stroke: vec2<f32>,
 };
 
 var<private> full: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
@@ -26,8 +53,20 @@ fn vert_main(
     instance: Instance,
 ) -> VertexOutput {
     var out: VertexOutput;
-    out.position = vec4<f32>(full[vertex_index]*instance.scale+instance.translation, 0.0, 1.0);
-    out.color = instance.color;
+    // Grow the quad to cover any part of the stroke outside the rect, and a pixel more for the anti-aliased edge.
+    let outset = (instance.stroke.y+1.0)*2.0/res.inner;
+    out.position = vec4<f32>(full[vertex_index]*(instance.scale+outset)+instance.translation, 0.0, 1.0);
+    out.scale = instance.scale;
+    out.translation = instance.translation;
+    out.fill_shape = instance.fill_shape;
+    out.fill_color0 = instance.fill_color0;
+    out.fill_color1 = instance.fill_color1;
+    out.fill_color2 = instance.fill_color2;
+    out.fill_color3 = instance.fill_color3;
+    out.fill_offsets = instance.fill_offsets;
+    out.corner_radii = instance.corner_radii;
+    out.stroke_color = instance.stroke_color;
+    out.stroke = instance.stroke;
     return out;
 }
 
@@ -37,5 +76,21 @@ fn vert_main(
 fn frag_main(
     in: VertexOutput,
 ) -> @location(0) vec4<f32> {
-    return in.color;
+    let res = res.inner;
+    let center = (in.translation*vec2<f32>(0.5, -0.5)+0.5)*res;
+    let half_size = in.scale*res*0.5;
+
+    let p = in.position.xy-center;
+    let distance = rounded_rect_distance(p, half_size, in.corner_radii);
+    let outer_edge = in.stroke.y;
+    let shape_coverage = coverage(distance-outer_edge);
+    if (shape_coverage <= 0.0) {
+        discard;
+    }
+
+    var color = fill_color(p, half_size, in.fill_shape, in.fill_color0, in.fill_color1, in.fill_color2, in.fill_color3, in.fill_offsets);
+    if (in.stroke.x > 0.0) {
+        color = mix_colors(color, in.stroke_color, coverage(outer_edge-in.stroke.x-distance));
+    }
+    return output_color(apply_coverage(color, shape_coverage));
 }
@@ -1,24 +1,48 @@
+use std::sync::Arc;
+
+use crate::{
+    error::{Error, Result},
+    readback::{self, RgbaImage},
+    target::RenderTarget,
+};
+
+enum Target {
+    Surface(wgpu::Surface),
+    Offscreen(wgpu::Texture),
+    // The caller owns the textures being drawn into and passes their views to the primitive renderers.
+    External,
+}
+
 pub struct Renderer {
-    surface: wgpu::Surface,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
+    target: Target,
+    device: Arc<wgpu::Device>,
+    queue: Arc<wgpu::Queue>,
     config: wgpu::SurfaceConfiguration,
+    sample_count: u32,
+    multisample_target: Option<(wgpu::Texture, wgpu::TextureView)>,
 }
 
-impl Renderer {
-    pub async fn new(window: &winit::window::Window) -> Self {
-        let size = window.inner_size();
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
-        let surface = unsafe { instance.create_surface(window) };
-        // let adapter = instance
-        //     .enumerate_adapters(wgpu::Backends::all())
-        //     .filter(|adapter| {
-        //         surface.get_preferred_format(&adapter).is_some()
-        //     })
-        //     .next()
-        //     .unwrap();
-        let backend = wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all);
-        let adapter = wgpu::util::initialize_adapter_from_env_or_default(&instance, backend, Some(&surface)).await.unwrap();
+/// Adapter, device and surface options used when a [`Renderer`] creates its own wgpu context.
+///
+/// The default matches the renderer's historical behaviour, including honouring the `WGPU_BACKEND`,
+/// `WGPU_POWER_PREF` and `WGPU_ADAPTER_NAME` environment variables.
+#[derive(Debug, Clone)]
+pub struct RendererDescriptor {
+    pub present_mode: wgpu::PresentMode,
+    pub limits: wgpu::Limits,
+    pub features: wgpu::Features,
+    pub power_preference: wgpu::PowerPreference,
+    pub backends: wgpu::Backends,
+    pub force_fallback_adapter: bool,
+    /// Used for the surface when the adapter supports it, otherwise the adapter's first supported format is.
+    pub preferred_format: Option<wgpu::TextureFormat>,
+    /// 1 disables MSAA. 2, 4 and 8 draw into a managed multisampled target, see [`Renderer::render_target`]; which
+    /// of them work depends on the adapter and format.
+    pub sample_count: u32,
+}
+
+impl Default for RendererDescriptor {
+    fn default() -> Self {
         let mut limits = wgpu::Limits::downlevel_webgl2_defaults();
         limits.max_storage_textures_per_shader_stage = 8;
         limits.max_texture_dimension_2d = 8192;
@@ -27,35 +51,240 @@ impl Renderer {
         limits.max_compute_workgroup_size_z = 64;
         limits.max_compute_workgroups_per_dimension = 65535;
         limits.max_compute_invocations_per_workgroup = 256;
-        let (device, queue) = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                features: wgpu::Features::empty(),
-                limits,
-                label: None,
-            },
-            None,
-        ).await.unwrap();
+
+        Self {
+            present_mode: wgpu::PresentMode::Fifo,
+            limits,
+            features: wgpu::Features::empty(),
+            power_preference: wgpu::util::power_preference_from_env().unwrap_or_default(),
+            backends: wgpu::util::backend_bits_from_env().unwrap_or_else(wgpu::Backends::all),
+            force_fallback_adapter: false,
+            preferred_format: None,
+            sample_count: 1,
+        }
+    }
+}
+
+impl Renderer {
+    pub async fn new(window: &winit::window::Window) -> Result<Self> {
+        Self::new_with_descriptor(window, &RendererDescriptor::default()).await
+    }
+
+    pub async fn new_with_descriptor(window: &winit::window::Window, descriptor: &RendererDescriptor) -> Result<Self> {
+        let size = window.inner_size();
+        let instance = wgpu::Instance::new(descriptor.backends);
+        let surface = unsafe { instance.create_surface(window) };
+        let adapter = Self::request_adapter(&instance, descriptor, Some(&surface)).await?;
+        let supported_formats = surface.get_supported_formats(&adapter);
+        let format = match descriptor.preferred_format {
+            Some(preferred_format) if supported_formats.contains(&preferred_format) => preferred_format,
+            _ => *supported_formats.first().ok_or(Error::NoSupportedSurfaceFormat)?,
+        };
+        Self::check_sample_count(&adapter, format, descriptor.sample_count)?;
+        let (device, queue) = Self::request_device(&adapter, descriptor).await?;
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: surface.get_supported_formats(&adapter)[0],
+            format,
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode: descriptor.present_mode,
         };
         surface.configure(&device, &config);
 
+        let mut renderer = Self {
+            target: Target::Surface(surface),
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+            config,
+            sample_count: 1,
+            multisample_target: None,
+        };
+        renderer.set_sample_count(descriptor.sample_count)?;
+        Ok(renderer)
+    }
+
+    /// Creates a renderer without a window that draws into an owned texture of the given size and format.
+    pub async fn new_headless(width: u32, height: u32, format: wgpu::TextureFormat) -> Result<Self> {
+        Self::new_headless_with_descriptor(width, height, format, &RendererDescriptor::default()).await
+    }
+
+    /// Like [`Renderer::new_headless`], but forces a software (CPU) adapter so output is reproducible across machines.
+    pub async fn new_headless_software(width: u32, height: u32, format: wgpu::TextureFormat) -> Result<Self> {
+        Self::new_headless_with_descriptor(width, height, format, &RendererDescriptor {
+            force_fallback_adapter: true,
+            ..Default::default()
+        }).await
+    }
+
+    pub async fn new_headless_with_descriptor(width: u32, height: u32, format: wgpu::TextureFormat, descriptor: &RendererDescriptor) -> Result<Self> {
+        let instance = wgpu::Instance::new(descriptor.backends);
+        let adapter = Self::request_adapter(&instance, descriptor, None).await?;
+        Self::check_sample_count(&adapter, format, descriptor.sample_count)?;
+        let (device, queue) = Self::request_device(&adapter, descriptor).await?;
+        let config = wgpu::SurfaceConfiguration {
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            format,
+            width,
+            height,
+            present_mode: descriptor.present_mode,
+        };
+        let texture = Self::generate_offscreen_texture(&device, &config);
+
+        let mut renderer = Self {
+            target: Target::Offscreen(texture),
+            device: Arc::new(device),
+            queue: Arc::new(queue),
+            config,
+            sample_count: 1,
+            multisample_target: None,
+        };
+        renderer.set_sample_count(descriptor.sample_count)?;
+        Ok(renderer)
+    }
+
+    /// Wraps a device and queue created elsewhere, e.g. to draw an overlay into an existing frame.
+    ///
+    /// The renderer has no target of its own; `format`, `width` and `height` describe the textures the caller will
+    /// pass to the primitive renderers and are what [`Renderer::config`] reports. MSAA is off until
+    /// [`Renderer::set_sample_count`] is called.
+    pub fn from_device(device: Arc<wgpu::Device>, queue: Arc<wgpu::Queue>, format: wgpu::TextureFormat, width: u32, height: u32) -> Self {
         Self {
-            surface,
+            target: Target::External,
             device,
             queue,
-            config,
+            config: wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                format,
+                width,
+                height,
+                present_mode: wgpu::PresentMode::Fifo,
+            },
+            sample_count: 1,
+            multisample_target: None,
+        }
+    }
+
+    async fn request_adapter(instance: &wgpu::Instance, descriptor: &RendererDescriptor, compatible_surface: Option<&wgpu::Surface>) -> Result<wgpu::Adapter> {
+        if !descriptor.force_fallback_adapter {
+            if let Some(adapter) = wgpu::util::initialize_adapter_from_env(instance, descriptor.backends) {
+                return Ok(adapter);
+            }
         }
+        instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: descriptor.power_preference,
+            force_fallback_adapter: descriptor.force_fallback_adapter,
+            compatible_surface,
+        }).await.ok_or(Error::NoAdapter)
+    }
+
+    async fn request_device(adapter: &wgpu::Adapter, descriptor: &RendererDescriptor) -> Result<(wgpu::Device, wgpu::Queue)> {
+        Ok(adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                features: descriptor.features,
+                limits: descriptor.limits.clone(),
+                label: None,
+            },
+            None,
+        ).await?)
+    }
+
+    fn check_sample_count(adapter: &wgpu::Adapter, format: wgpu::TextureFormat, sample_count: u32) -> Result<()> {
+        let required = wgpu::TextureFormatFeatureFlags::MULTISAMPLE | wgpu::TextureFormatFeatureFlags::MULTISAMPLE_RESOLVE;
+        if sample_count > 1 && !adapter.get_texture_format_features(format).flags.contains(required) {
+            return Err(Error::UnsupportedSampleCount(sample_count));
+        }
+        Ok(())
+    }
+
+    fn generate_multisample_target(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration, sample_count: u32) -> Option<(wgpu::Texture, wgpu::TextureView)> {
+        if sample_count == 1 {
+            return None;
+        }
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Multisample Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                ..Default::default()
+            },
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+        });
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        Some((texture, view))
+    }
+
+    fn generate_offscreen_texture(device: &wgpu::Device, config: &wgpu::SurfaceConfiguration) -> wgpu::Texture {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Offscreen Target"),
+            size: wgpu::Extent3d {
+                width: config.width,
+                height: config.height,
+                ..Default::default()
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: config.format,
+            usage: config.usage,
+        })
     }
 
     pub fn device(&self) -> &wgpu::Device { &self.device }
-    pub fn surface(&self) -> &wgpu::Surface { &self.surface }
     pub fn queue(&self) -> &wgpu::Queue { &self.queue }
     pub fn config(&self) ->&wgpu::SurfaceConfiguration { &self.config }
+    pub fn sample_count(&self) -> u32 { self.sample_count }
+
+    /// Switches MSAA on or off. Primitive renderers must be created with the same sample count, so existing ones need
+    /// recreating afterwards.
+    pub fn set_sample_count(&mut self, sample_count: u32) -> Result<()> {
+        if ![1, 2, 4, 8].contains(&sample_count) {
+            return Err(Error::UnsupportedSampleCount(sample_count));
+        }
+        self.sample_count = sample_count;
+        self.multisample_target = Self::generate_multisample_target(&self.device, &self.config, sample_count);
+        Ok(())
+    }
+
+    /// What primitive renderers should draw into for their output to end up in `view`, such as the current surface
+    /// texture's. With MSAA on this is the managed multisampled target, resolving into `view`.
+    pub fn render_target<'a>(&'a self, view: &'a wgpu::TextureView) -> RenderTarget<'a> {
+        match &self.multisample_target {
+            Some((_, multisample_view)) => RenderTarget::multisampled(multisample_view, view),
+            None => view.into(),
+        }
+    }
+
+    pub fn surface(&self) -> Option<&wgpu::Surface> {
+        match &self.target {
+            Target::Surface(surface) => Some(surface),
+            Target::Offscreen(_) | Target::External => None,
+        }
+    }
+
+    /// The surface's next texture to draw into and present.
+    pub fn get_current_texture(&self) -> Result<wgpu::SurfaceTexture> {
+        Ok(self.surface().ok_or(Error::NoSurface)?.get_current_texture()?)
+    }
+
+    pub fn offscreen_texture(&self) -> Option<&wgpu::Texture> {
+        match &self.target {
+            Target::Offscreen(texture) => Some(texture),
+            Target::Surface(_) | Target::External => None,
+        }
+    }
+
+    pub fn offscreen_view(&self) -> Option<wgpu::TextureView> {
+        self.offscreen_texture().map(|texture| texture.create_view(&wgpu::TextureViewDescriptor::default()))
+    }
+
+    /// Reads back a texture matching the renderer's configured size and format, such as the offscreen target.
+    pub async fn read_texture(&self, texture: &wgpu::Texture) -> Result<RgbaImage> {
+        readback::read_texture(&self.device, &self.queue, texture, self.config.width, self.config.height, self.config.format).await
+    }
 
     pub fn resize(&mut self, width: u32, height: u32) {
         if width > 0 && height > 0  {
@@ -66,6 +295,11 @@ impl Renderer {
     }
 
     pub fn reconfigure(&mut self) {
-        self.surface.configure(&self.device, &self.config);
+        match &mut self.target {
+            Target::Surface(surface) => surface.configure(&self.device, &self.config),
+            Target::Offscreen(texture) => *texture = Self::generate_offscreen_texture(&self.device, &self.config),
+            Target::External => {},
+        }
+        self.multisample_target = Self::generate_multisample_target(&self.device, &self.config, self.sample_count);
     }
-}
\ No newline at end of file
+}
@@ -1,3 +1,37 @@
 use crate::typed_uniform;
 
-typed_uniform!(ResolutionBuffer, [f32; 2], "Resolution Buffer");
\ No newline at end of file
+typed_uniform!(ResolutionBuffer, [f32; 2], "Resolution Buffer");
+
+impl ResolutionBuffer {
+    /// Layout of a bind group holding just the resolution, for both shader stages.
+    pub fn bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+        device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Resolution Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ]
+        })
+    }
+
+    pub fn bind_group(&self, device: &wgpu::Device, layout: &wgpu::BindGroupLayout) -> wgpu::BindGroup {
+        device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Resolution Bind Group"),
+            layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: self.binding(),
+                }
+            ],
+        })
+    }
+}
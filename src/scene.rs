@@ -0,0 +1,260 @@
+use std::ops::Range;
+
+use crate::{
+    blend::BlendMode,
+    clip::{ClipStack, ScissorRect},
+    ellipse::{EllipseBuffer, EllipseRenderer},
+    freeform_2dcapsule::{Freeform2DCapsule, Freeform2DCapsuleRenderer},
+    line::{LineRaw, LineRenderer},
+    rect::{Points, RectRaw, RectRenderer},
+    resolution_buffer::ResolutionBuffer,
+    shadow::{ShadowRaw, ShadowRenderer},
+    target::RenderTarget,
+    text::TextRenderer,
+};
+
+pub enum Primitive {
+    Rect(RectRaw),
+    Ellipse(EllipseBuffer),
+    Shadow(ShadowRaw),
+    Line(LineRaw),
+    Capsule(Freeform2DCapsule),
+    /// Glyph outline lines, as produced by `ttf_outline`, filled as one shape.
+    Text(Vec<LineRaw>),
+}
+
+impl From<RectRaw> for Primitive {
+    fn from(rect: RectRaw) -> Self { Primitive::Rect(rect) }
+}
+
+impl From<EllipseBuffer> for Primitive {
+    fn from(ellipse: EllipseBuffer) -> Self { Primitive::Ellipse(ellipse) }
+}
+
+impl From<ShadowRaw> for Primitive {
+    fn from(shadow: ShadowRaw) -> Self { Primitive::Shadow(shadow) }
+}
+
+impl From<LineRaw> for Primitive {
+    fn from(line: LineRaw) -> Self { Primitive::Line(line) }
+}
+
+impl From<Freeform2DCapsule> for Primitive {
+    fn from(capsule: Freeform2DCapsule) -> Self { Primitive::Capsule(capsule) }
+}
+
+/// A draw list of mixed primitives.
+///
+/// Primitives are painted in ascending `z`; primitives with equal `z` are painted in the order they were pushed. Each
+/// primitive is clipped to the clip rect current when it was pushed, see [`Scene::push_clip`].
+#[derive(Default)]
+pub struct Scene {
+    items: Vec<(i32, Option<Points>, Primitive)>,
+    clips: ClipStack,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push<P: Into<Primitive>>(&mut self, z: i32, primitive: P) {
+        self.items.push((z, self.clips.current(), primitive.into()));
+    }
+
+    /// Clips the primitives pushed until the matching [`Scene::pop_clip`] to `clip`, within any clip already pushed.
+    pub fn push_clip<P: Into<Points>>(&mut self, clip: P) {
+        self.clips.push(clip);
+    }
+
+    pub fn pop_clip(&mut self) -> Option<Points> {
+        self.clips.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.clips.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Rect,
+    Ellipse,
+    Shadow,
+    Line,
+    Capsule,
+    Text,
+}
+
+struct Batch {
+    kind: Kind,
+    instances: Range<u32>,
+    scissor: Option<ScissorRect>,
+}
+
+/// Draws a [`Scene`] with one renderer per primitive kind.
+///
+/// Consecutive primitives of the same kind become one instanced draw, and all draws share a single render pass,
+/// except that text needs stencil passes of its own and so splits the pass wherever it appears. A change of clip rect
+/// also starts a new draw.
+pub struct SceneRenderer {
+    rect_renderer: RectRenderer,
+    ellipse_renderer: EllipseRenderer,
+    shadow_renderer: ShadowRenderer,
+    line_renderer: LineRenderer,
+    capsule_renderer: Freeform2DCapsuleRenderer,
+    text_renderer: TextRenderer,
+    batches: Vec<Batch>,
+    resx: u32,
+    resy: u32,
+}
+
+impl SceneRenderer {
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer, resx: u32, resy: u32) -> Self {
+        Self {
+            rect_renderer: RectRenderer::new(device, texture_format, sample_count, blend_mode, resolution_buffer),
+            ellipse_renderer: EllipseRenderer::new(device, texture_format, sample_count, blend_mode, resolution_buffer),
+            shadow_renderer: ShadowRenderer::new(device, texture_format, sample_count, blend_mode, resolution_buffer),
+            line_renderer: LineRenderer::new(device, texture_format, sample_count, blend_mode),
+            capsule_renderer: Freeform2DCapsuleRenderer::new(device, texture_format, sample_count, blend_mode, resolution_buffer),
+            text_renderer: TextRenderer::new(device, texture_format, sample_count, blend_mode, resx, resy),
+            batches: Vec::new(),
+            resx,
+            resy,
+        }
+    }
+
+    pub fn on_resize(&mut self, device: &wgpu::Device, resx: u32, resy: u32) {
+        self.text_renderer.on_resize(device, resx, resy);
+        self.resx = resx;
+        self.resy = resy;
+    }
+
+    /// Sorts `scene` into painter's order, uploads its instances and works out the draw batches.
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, scene: &Scene) {
+        let mut order: Vec<&(i32, Option<Points>, Primitive)> = scene.items.iter().collect();
+        order.sort_by_key(|(z, _, _)| *z);
+
+        let mut rects = Vec::new();
+        let mut ellipses = Vec::new();
+        let mut shadows = Vec::new();
+        let mut lines = Vec::new();
+        let mut capsules = Vec::new();
+        let mut text_lines = Vec::new();
+        self.batches.clear();
+
+        for (_, clip, primitive) in order {
+            let (kind, start, end) = match primitive {
+                Primitive::Rect(rect) => {
+                    rects.push(*rect);
+                    (Kind::Rect, rects.len()-1, rects.len())
+                },
+                Primitive::Ellipse(ellipse) => {
+                    ellipses.push(*ellipse);
+                    (Kind::Ellipse, ellipses.len()-1, ellipses.len())
+                },
+                Primitive::Shadow(shadow) => {
+                    shadows.push(*shadow);
+                    (Kind::Shadow, shadows.len()-1, shadows.len())
+                },
+                Primitive::Line(line) => {
+                    lines.push(*line);
+                    (Kind::Line, lines.len()-1, lines.len())
+                },
+                Primitive::Capsule(capsule) => {
+                    capsules.push(*capsule);
+                    (Kind::Capsule, capsules.len()-1, capsules.len())
+                },
+                Primitive::Text(outline) => {
+                    let start = text_lines.len();
+                    text_lines.extend_from_slice(outline);
+                    (Kind::Text, start, text_lines.len())
+                },
+            };
+            let (start, end) = (start as u32, end as u32);
+            let scissor = clip.map(|clip| ScissorRect::from_points(clip, self.resx, self.resy));
+
+            match self.batches.last_mut() {
+                Some(batch) if batch.kind == kind && batch.instances.end == start && batch.scissor == scissor => batch.instances.end = end,
+                _ => self.batches.push(Batch {
+                    kind,
+                    instances: start..end,
+                    scissor,
+                }),
+            }
+        }
+
+        self.rect_renderer.set_rect_buffer(device, queue, &rects);
+        self.ellipse_renderer.set_ellipse_buffer(device, queue, &ellipses);
+        self.shadow_renderer.set_shadow_buffer(device, queue, &shadows);
+        self.line_renderer.set_line_buffer(device, queue, &lines);
+        self.capsule_renderer.set_capsule_buffer(device, queue, &capsules);
+        self.text_renderer.set_line_buffer(device, queue, &text_lines);
+    }
+
+    /// Unclipped primitives are limited to the target's own scissor, if it has one.
+    pub fn render<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        let target = target.into();
+        let full = ScissorRect {
+            x: 0,
+            y: 0,
+            width: self.resx,
+            height: self.resy,
+        };
+        let mut pending_loadop = Some(loadop);
+        let mut batches = self.batches.iter().peekable();
+
+        while let Some(batch) = batches.peek() {
+            let loadop = pending_loadop.take().unwrap_or(wgpu::LoadOp::Load);
+
+            if batch.kind == Kind::Text {
+                self.text_renderer.render_range(encoder, target.with_scissor(batch.scissor.or(target.scissor)), loadop, batch.instances.clone());
+                batches.next();
+                continue;
+            }
+
+            let mut render_pass = Self::begin_render_pass(encoder, target, loadop);
+            while let Some(batch) = batches.next_if(|batch| batch.kind != Kind::Text) {
+                let instances = batch.instances.clone();
+                let scissor = batch.scissor.or(target.scissor).unwrap_or(full);
+                if scissor.is_empty() {
+                    continue;
+                }
+                scissor.apply(&mut render_pass);
+                match batch.kind {
+                    Kind::Rect => self.rect_renderer.draw(&mut render_pass, instances),
+                    Kind::Ellipse => self.ellipse_renderer.draw(&mut render_pass, instances),
+                    Kind::Shadow => self.shadow_renderer.draw(&mut render_pass, instances),
+                    Kind::Line => self.line_renderer.draw(&mut render_pass, instances),
+                    Kind::Capsule => self.capsule_renderer.draw(&mut render_pass, instances),
+                    Kind::Text => unreachable!(),
+                }
+            }
+        }
+
+        // Nothing was drawn, but the caller may still expect the target to be cleared.
+        if let Some(loadop) = pending_loadop {
+            Self::begin_render_pass(encoder, target, loadop);
+        }
+    }
+
+    fn begin_render_pass<'a>(encoder: &'a mut wgpu::CommandEncoder, target: RenderTarget<'a>, loadop: wgpu::LoadOp<wgpu::Color>) -> wgpu::RenderPass<'a> {
+        // No scissor here, every batch sets its own.
+        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Scene Render Pass"),
+            color_attachments: &[
+                Some(target.color_attachment(loadop))
+            ],
+            depth_stencil_attachment: None,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+use crate::{
+    blend::BlendMode,
+    color::is_srgb,
+};
+
+const COMMON: &str = include_str!("common.wgsl");
+
+/// Builds a shader module that can use the helpers in `common.wgsl`, specialised for drawing into `target_format`
+/// with `blend_mode`.
+pub(crate) fn create_shader_module(device: &wgpu::Device, label: &str, source: &str, target_format: wgpu::TextureFormat, blend_mode: BlendMode) -> wgpu::ShaderModule {
+    with_header(device, label, source, is_srgb(target_format), blend_mode.premultiplies_in_shader(), blend_mode.premultiplied_input())
+}
+
+/// Builds a shader module for pipelines that only write the stencil, which can still use the shape helpers in
+/// `common.wgsl`.
+pub(crate) fn create_stencil_shader_module(device: &wgpu::Device, label: &str, source: &str) -> wgpu::ShaderModule {
+    with_header(device, label, source, false, false, false)
+}
+
+fn with_header(device: &wgpu::Device, label: &str, source: &str, srgb_target: bool, premultiply_output: bool, premultiplied_input: bool) -> wgpu::ShaderModule {
+    let header = format!(
+        "let SRGB_TARGET: bool = {};\nlet PREMULTIPLY_OUTPUT: bool = {};\nlet PREMULTIPLIED_INPUT: bool = {};\n",
+        srgb_target,
+        premultiply_output,
+        premultiplied_input,
+    );
+    device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some(label),
+        source: wgpu::ShaderSource::Wgsl([header.as_str(), COMMON, source].concat().into()),
+    })
+}
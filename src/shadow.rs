@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+use wgpu::BufferAddress;
+
+use crate::{
+    blend::BlendMode,
+    color::Color,
+    instance_buffer::InstanceBuffer,
+    mask,
+    rect::{CornerRadii, SizeAndCenter},
+    resolution_buffer::ResolutionBuffer,
+    shader,
+    target::RenderTarget,
+};
+
+/// Draws blurred rounded-rect shadows analytically, without an extra blur pass.
+pub struct ShadowRenderer {
+    shadow_buffer: InstanceBuffer<ShadowRaw>,
+    pipeline: wgpu::RenderPipeline,
+    resolution_bind_group: wgpu::BindGroup,
+}
+
+impl ShadowRenderer {
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, None)
+    }
+
+    /// For drawing inside a [`MaskRenderer`](crate::mask::MaskRenderer)'s masks.
+    pub fn new_masked(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer) -> Self {
+        Self::with_depth_stencil(device, texture_format, sample_count, blend_mode, resolution_buffer, Some(mask::masked_depth_stencil_state()))
+    }
+
+    fn with_depth_stencil(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resolution_buffer: &ResolutionBuffer, depth_stencil: Option<wgpu::DepthStencilState>) -> Self {
+        let shader = shader::create_shader_module(device, "Shadow Shader", include_str!("shadow.wgsl"), texture_format, blend_mode);
+
+        let shadow_buffer = InstanceBuffer::new(device, "Shadow Buffer");
+
+        let shadow_layout = wgpu::VertexBufferLayout {
+            array_stride: SHADOW_RAW_SIZE,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &[
+                wgpu::VertexAttribute {
+                    offset: 0,
+                    shader_location: 0,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 2]>() as BufferAddress,
+                    shader_location: 1,
+                    format: wgpu::VertexFormat::Float32x2
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 4]>() as BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as BufferAddress,
+                    shader_location: 3,
+                    format: wgpu::VertexFormat::Float32x4
+                },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 12]>() as BufferAddress,
+                    shader_location: 4,
+                    format: wgpu::VertexFormat::Float32x4
+                }
+            ]
+        };
+
+        let resolution_bind_group_layout = ResolutionBuffer::bind_group_layout(device);
+        let resolution_bind_group = resolution_buffer.bind_group(device, &resolution_bind_group_layout);
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("Shadow Pipeline"),
+            layout: Some(&device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+                label: Some("Shadow Pipeline Layout"),
+                bind_group_layouts: &[
+                    &resolution_bind_group_layout
+                ],
+                push_constant_ranges: &[],
+            })),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vert_main",
+                buffers: &[
+                    shadow_layout
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "frag_main",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: texture_format,
+                    blend: Some(blend_mode.blend_state()),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })]
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                strip_index_format: None,
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: Some(wgpu::Face::Back),
+                polygon_mode: wgpu::PolygonMode::Fill,
+                unclipped_depth: false,
+                conservative: false,
+            },
+            depth_stencil,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+            multiview: None
+        });
+
+        Self {
+            shadow_buffer,
+            pipeline,
+            resolution_bind_group,
+        }
+    }
+
+    pub fn set_shadow_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[ShadowRaw]) {
+        self.shadow_buffer.set(device, queue, data);
+    }
+
+    pub fn shadow_buffer(&self) -> &InstanceBuffer<ShadowRaw> {
+        &self.shadow_buffer
+    }
+
+    pub fn shadow_buffer_mut(&mut self) -> &mut InstanceBuffer<ShadowRaw> {
+        &mut self.shadow_buffer
+    }
+
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        let target = target.into();
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("Shadow Render Pass"),
+            color_attachments: &[
+                Some(target.color_attachment(loadop))
+            ],
+            depth_stencil_attachment: None,
+        });
+        if target.apply_scissor(&mut render_pass) {
+            self.draw(&mut render_pass, 0..self.shadow_buffer.len());
+        }
+    }
+
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.resolution_bind_group, &[]);
+        render_pass.set_vertex_buffer(0, self.shadow_buffer.buffer().slice(..));
+        render_pass.draw(0..6, instances);
+    }
+}
+
+/// A shadow following CSS `box-shadow` semantics, cast by the box at `sizing`.
+///
+/// `offset`, `blur_radius` and `spread` are in pixels, with the offset y down as in CSS. Spread grows the box on every
+/// side, or shrinks it when negative. Corner radii grow with it as in CSS, by less of the spread the smaller they are
+/// compared to it, so square corners stay square. The shadow is not cut out beneath the box, so draw it before the box
+/// itself.
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowDescriptor {
+    pub sizing: SizeAndCenter,
+    pub offset: [f32; 2],
+    pub blur_radius: f32,
+    pub spread: f32,
+    pub color: Color,
+    pub corner_radii: CornerRadii,
+}
+
+const SHADOW_RAW_SIZE: BufferAddress = std::mem::size_of::<ShadowRaw>() as BufferAddress;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ShadowRaw {
+    pub scale: [f32; 2],
+    pub translation: [f32; 2],
+    /// Offset, blur radius and spread, in pixels.
+    pub offset_blur_spread: [f32; 4],
+    pub color: [f32; 4],
+    /// Top left, top right, bottom right, bottom left, in pixels, before the spread is applied.
+    pub corner_radii: [f32; 4],
+}
+
+impl From<ShadowDescriptor> for ShadowRaw {
+    fn from(descriptor: ShadowDescriptor) -> Self {
+        Self {
+            scale: [descriptor.sizing.sx, descriptor.sizing.sy],
+            translation: [descriptor.sizing.cx, descriptor.sizing.cy],
+            offset_blur_spread: [descriptor.offset[0], descriptor.offset[1], descriptor.blur_radius, descriptor.spread],
+            color: descriptor.color.into(),
+            corner_radii: descriptor.corner_radii.into(),
+        }
+    }
+}
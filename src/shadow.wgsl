@@ -0,0 +1,141 @@
+struct Vec2f32 {
+    inner: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> res: Vec2f32;
+
+struct Instance {
+    @location(0) scale: vec2<f32>,
+    @location(1) translation: vec2<f32>,
+    // Offset, blur radius and spread, all in pixels.
+    @location(2) offset_blur_spread: vec4<f32>,
+    @location(3) color: vec4<f32>,
+    @location(4) corner_radii: vec4<f32>,
+};
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+    @location(0) color: vec4<f32>,
+    // Everything below is in framebuffer pixels, for the box after offset and spread.
+    @location(1) center: vec2<f32>,
+    @location(2) half_size: vec2<f32>,
+    @location(3) corner_radii: vec4<f32>,
+    @location(4) blur: f32,
+};
+
+var<private> full: array<vec2<f32>, 6> = array<vec2<f32>, 6>(
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, -1.0),
+    vec2<f32>(1.0, 1.0),
+    vec2<f32>(-1.0, 1.0),
+);
+
+// Radii of the box grown by `spread`, as CSS does for `box-shadow`: radii much smaller than a positive spread grow
+// by less of it, so sharp corners stay sharp.
+fn spread_radii(radii: vec4<f32>, spread: f32) -> vec4<f32> {
+    if (spread <= 0.0) {
+        return max(radii+spread, vec4<f32>(0.0));
+    }
+    let ratio = min(radii/spread, vec4<f32>(1.0)) - 1.0;
+    return radii+spread*(1.0+ratio*ratio*ratio);
+}
+
+// Vertex shader
+
+@vertex
+fn vert_main(
+    @builtin(vertex_index) vertex_index: u32,
+    instance: Instance,
+) -> VertexOutput {
+    var out: VertexOutput;
+    let offset = instance.offset_blur_spread.xy;
+    let blur = instance.offset_blur_spread.z;
+    let spread = instance.offset_blur_spread.w;
+
+    let center = (instance.translation*vec2<f32>(0.5, -0.5)+0.5)*res.inner+offset;
+    let half_size = max(instance.scale*res.inner*0.5+spread, vec2<f32>(0.0));
+    // The gaussian is negligible three standard deviations (half the blur radius each) out, plus a pixel for the edge.
+    let margin = blur*1.5+1.0;
+    let quad_translation = instance.translation+offset*vec2<f32>(2.0, -2.0)/res.inner;
+    let quad_scale = (half_size+margin)*2.0/res.inner;
+
+    out.position = vec4<f32>(full[vertex_index]*quad_scale+quad_translation, 0.0, 1.0);
+    out.color = instance.color;
+    out.center = center;
+    out.half_size = half_size;
+    out.corner_radii = spread_radii(instance.corner_radii, spread);
+    out.blur = blur;
+    return out;
+}
+
+// Fragment shader
+
+fn gaussian(x: f32, sigma: f32) -> f32 {
+    return exp(-(x*x)/(2.0*sigma*sigma))/(2.5066283*sigma);
+}
+
+// Abramowitz and Stegun's approximation, good to about 5e-4.
+fn erf(x: vec2<f32>) -> vec2<f32> {
+    let s = sign(x);
+    let a = abs(x);
+    var r = 1.0+(0.278393+(0.230389+0.078108*(a*a))*a)*a;
+    r = r*r;
+    return s-s/(r*r);
+}
+
+// The blurred box integrated exactly along x for the horizontal slice of the rounded box at `y`.
+fn shadow_x(x: f32, y: f32, sigma: f32, corner: f32, half_size: vec2<f32>) -> f32 {
+    let delta = min(half_size.y-corner-abs(y), 0.0);
+    let curved = half_size.x-corner+sqrt(max(0.0, corner*corner-delta*delta));
+    let integral = 0.5+0.5*erf((x+vec2<f32>(-curved, curved))*(0.70710678/sigma));
+    return integral.y-integral.x;
+}
+
+// Coverage of a rounded box convolved with a gaussian, integrating along y with a few samples. After Evan Wallace's
+// "Fast Rounded Rectangle Shadows".
+fn rounded_box_shadow(p: vec2<f32>, half_size: vec2<f32>, sigma: f32, corner: f32) -> f32 {
+    let low = p.y-half_size.y;
+    let high = p.y+half_size.y;
+    let start = clamp(-3.0*sigma, low, high);
+    let end = clamp(3.0*sigma, low, high);
+    let step = (end-start)/4.0;
+    var y = start+step*0.5;
+    var value = 0.0;
+    for (var i = 0; i < 4; i = i+1) {
+        value = value+shadow_x(p.x, p.y-y, sigma, corner, half_size)*gaussian(y, sigma)*step;
+        y = y+step;
+    }
+    return value;
+}
+
+@fragment
+fn frag_main(
+    in: VertexOutput,
+) -> @location(0) vec4<f32> {
+    let p = in.position.xy-in.center;
+    // Like CSS, the blur radius is twice the standard deviation.
+    let sigma = in.blur*0.5;
+
+    var shadow_coverage: f32;
+    if (sigma < 0.25) {
+        shadow_coverage = coverage(rounded_rect_distance(p, in.half_size, in.corner_radii));
+    } else {
+        // One radius per quadrant, as rounded_rect_distance picks it.
+        var corner = in.corner_radii.w;
+        if (p.y < 0.0) {
+            corner = select(in.corner_radii.y, in.corner_radii.x, p.x < 0.0);
+        } else if (p.x > 0.0) {
+            corner = in.corner_radii.z;
+        }
+        corner = min(corner, min(in.half_size.x, in.half_size.y));
+        shadow_coverage = rounded_box_shadow(p, in.half_size, sigma, corner);
+    }
+
+    if (shadow_coverage <= 0.0) {
+        discard;
+    }
+    return output_color(apply_coverage(in.color, shadow_coverage));
+}
@@ -0,0 +1,59 @@
+use crate::color::Color;
+
+/// Where a stroke sits relative to the edge of the shape it outlines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StrokeAlignment {
+    /// Entirely within the shape, like a CSS border.
+    #[default]
+    Inside,
+    /// Straddling the edge.
+    Center,
+    /// Entirely outside the shape, which is drawn larger to make room for it.
+    Outside,
+}
+
+impl StrokeAlignment {
+    /// Fraction of the stroke width that lies outside the shape's edge.
+    pub fn outset(self) -> f32 {
+        match self {
+            StrokeAlignment::Inside => 0.0,
+            StrokeAlignment::Center => 0.5,
+            StrokeAlignment::Outside => 1.0,
+        }
+    }
+}
+
+/// An outline with its width given in pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub color: Color,
+    pub alignment: StrokeAlignment,
+}
+
+impl Stroke {
+    pub const NONE: Stroke = Stroke {
+        width: 0.0,
+        color: Color::TRANSPARENT,
+        alignment: StrokeAlignment::Inside,
+    };
+
+    pub fn new(width: f32, color: Color, alignment: StrokeAlignment) -> Self {
+        Self {
+            width,
+            color,
+            alignment,
+        }
+    }
+
+    /// Width and outset in pixels, as the shaders take them.
+    pub(crate) fn width_and_outset(self) -> [f32; 2] {
+        [self.width, self.width*self.alignment.outset()]
+    }
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Stroke::NONE
+    }
+}
@@ -0,0 +1,65 @@
+use crate::clip::ScissorRect;
+
+/// The colour attachment a renderer draws into.
+///
+/// A plain view converts into a target directly. When multisampling, `view` is the multisampled texture and
+/// `resolve_target` the single-sampled one it resolves into, such as the surface. See
+/// [`Renderer::render_target`](crate::renderer::Renderer::render_target).
+#[derive(Debug, Clone, Copy)]
+pub struct RenderTarget<'a> {
+    pub view: &'a wgpu::TextureView,
+    pub resolve_target: Option<&'a wgpu::TextureView>,
+    /// Limits drawing to part of the target, see [`ClipStack`](crate::clip::ClipStack).
+    pub scissor: Option<ScissorRect>,
+}
+
+impl<'a> RenderTarget<'a> {
+    pub fn multisampled(view: &'a wgpu::TextureView, resolve_target: &'a wgpu::TextureView) -> Self {
+        Self {
+            view,
+            resolve_target: Some(resolve_target),
+            scissor: None,
+        }
+    }
+
+    pub fn with_scissor(self, scissor: Option<ScissorRect>) -> Self {
+        Self {
+            scissor,
+            ..self
+        }
+    }
+
+    pub(crate) fn color_attachment(self, loadop: wgpu::LoadOp<wgpu::Color>) -> wgpu::RenderPassColorAttachment<'a> {
+        wgpu::RenderPassColorAttachment {
+            view: self.view,
+            resolve_target: self.resolve_target,
+            ops: wgpu::Operations {
+                load: loadop,
+                store: true,
+            }
+        }
+    }
+
+    /// Returns `false` without touching the pass when the scissor is empty, in which case nothing should be drawn, as
+    /// wgpu rejects empty scissor rects.
+    pub(crate) fn apply_scissor(self, render_pass: &mut wgpu::RenderPass) -> bool {
+        match self.scissor {
+            Some(scissor) if scissor.is_empty() => false,
+            Some(scissor) => {
+                scissor.apply(render_pass);
+                true
+            },
+            None => true,
+        }
+    }
+}
+
+impl<'a> From<&'a wgpu::TextureView> for RenderTarget<'a> {
+    fn from(view: &'a wgpu::TextureView) -> Self {
+        Self {
+            view,
+            resolve_target: None,
+            scissor: None,
+        }
+    }
+}
@@ -1,26 +1,30 @@
+use std::ops::Range;
+
 use wgpu::{BufferAddress, include_wgsl};
 
-use crate::line::{LINE_RAW_SIZE, LineRaw};
+use crate::{
+    blend::BlendMode,
+    instance_buffer::InstanceBuffer,
+    line::{LINE_RAW_SIZE, LineRaw},
+    mask,
+    shader,
+    target::RenderTarget,
+};
 
 pub struct TextRenderer {
-    line_buffer: wgpu::Buffer,
-    line_count: u32,
+    line_buffer: InstanceBuffer<LineRaw>,
     stencil_pipeline: wgpu::RenderPipeline,
     stencil_texture: wgpu::Texture,
+    sample_count: u32,
     render_pipeline: wgpu::RenderPipeline,
 }
 
 impl TextRenderer {
-    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, max_line_count: u64, resx: u32, resy: u32) -> Self {
+    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat, sample_count: u32, blend_mode: BlendMode, resx: u32, resy: u32) -> Self {
         let stencil_shader = device.create_shader_module(include_wgsl!("text.wgsl"));
-        let render_shader = device.create_shader_module(include_wgsl!("text_render.wgsl"));
+        let render_shader = shader::create_shader_module(device, "Text Render Shader", include_str!("text_render.wgsl"), texture_format, blend_mode);
 
-        let line_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some("Text Line Buffer"),
-            size: LINE_RAW_SIZE*max_line_count,
-            usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
-            mapped_at_creation: false,
-        });
+        let line_buffer = InstanceBuffer::new(device, "Text Line Buffer");
 
         let line_layout = wgpu::VertexBufferLayout {
             array_stride: LINE_RAW_SIZE,
@@ -46,7 +50,7 @@ impl TextRenderer {
             pass_op: wgpu::StencilOperation::Invert,
         };
 
-        let stencil_texture = Self::generate_stencil_texture(device, resx, resy);
+        let stencil_texture = Self::generate_stencil_texture(device, resx, resy, sample_count);
 
         let stencil_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Text Pipeline"),
@@ -77,7 +81,7 @@ impl TextRenderer {
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth24PlusStencil8,
+                format: mask::STENCIL_FORMAT,
                 depth_write_enabled: false,
                 depth_compare: wgpu::CompareFunction::Always,
                 stencil: wgpu::StencilState {
@@ -89,7 +93,7 @@ impl TextRenderer {
                 bias: wgpu::DepthBiasState::default()
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -120,7 +124,7 @@ impl TextRenderer {
                 entry_point: "frag_main",
                 targets: &[Some(wgpu::ColorTargetState {
                     format: texture_format,
-                    blend: Some(wgpu::BlendState::REPLACE),
+                    blend: Some(blend_mode.blend_state()),
                     write_mask: wgpu::ColorWrites::ALL,
                 })]
             }),
@@ -134,7 +138,7 @@ impl TextRenderer {
                 conservative: false,
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth24PlusStencil8,
+                format: mask::STENCIL_FORMAT,
                 depth_write_enabled: false,
                 depth_compare: wgpu::CompareFunction::Always,
                 stencil: wgpu::StencilState {
@@ -146,7 +150,7 @@ impl TextRenderer {
                 bias: wgpu::DepthBiasState::default()
             }),
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -155,39 +159,39 @@ impl TextRenderer {
 
         Self {
             line_buffer,
-            line_count: 0,
             stencil_pipeline,
             stencil_texture,
+            sample_count,
             render_pipeline
         }
     }
 
-    pub fn set_line_buffer(&mut self, queue: &wgpu::Queue, data: &[LineRaw]) {
-        self.line_count = data.len() as u32;
-        queue.write_buffer(&self.line_buffer, 0, bytemuck::cast_slice::<LineRaw, u8>(data));
+    pub fn set_line_buffer(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, data: &[LineRaw]) {
+        self.line_buffer.set(device, queue, data);
     }
 
-    fn generate_stencil_texture(device: &wgpu::Device, resx: u32, resy: u32) -> wgpu::Texture {
-        device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Text Stencil"),
-            size: wgpu::Extent3d {
-                width: resx,
-                height: resy,
-                ..Default::default()
-            },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth24PlusStencil8,
-            usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-        })
+    pub fn line_buffer(&self) -> &InstanceBuffer<LineRaw> {
+        &self.line_buffer
+    }
+
+    pub fn line_buffer_mut(&mut self) -> &mut InstanceBuffer<LineRaw> {
+        &mut self.line_buffer
+    }
+
+    fn generate_stencil_texture(device: &wgpu::Device, resx: u32, resy: u32, sample_count: u32) -> wgpu::Texture {
+        mask::create_stencil_texture(device, "Text Stencil", resx, resy, sample_count)
     }
 
     pub fn on_resize(&mut self, device: &wgpu::Device, resx: u32, resy: u32) {
-        self.stencil_texture = Self::generate_stencil_texture(device, resx, resy);
+        self.stencil_texture = Self::generate_stencil_texture(device, resx, resy, self.sample_count);
+    }
+
+    pub fn render_all<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>) {
+        self.render_range(encoder, target, loadop, 0..self.line_buffer.len());
     }
 
-    pub fn render_all(&self, encoder: &mut wgpu::CommandEncoder, view: &wgpu::TextureView, loadop: wgpu::LoadOp<wgpu::Color>) {
+    /// Fills the outline formed by `lines`, a sub-range of the line buffer. Needs passes of its own for the stencil.
+    pub fn render_range<'a>(&self, encoder: &mut wgpu::CommandEncoder, target: impl Into<RenderTarget<'a>>, loadop: wgpu::LoadOp<wgpu::Color>, lines: Range<u32>) {
         let stencil_texture_view = self.stencil_texture.create_view(&wgpu::TextureViewDescriptor::default());
         let mut stencil_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Text Stencil Pass"),
@@ -202,23 +206,17 @@ impl TextRenderer {
             })
         });
         stencil_pass.set_pipeline(&self.stencil_pipeline);
-        stencil_pass.set_vertex_buffer(0, self.line_buffer.slice(..));
+        stencil_pass.set_vertex_buffer(0, self.line_buffer.buffer().slice(..));
         stencil_pass.set_stencil_reference(0);
-        stencil_pass.draw(0..3, 0..self.line_count);
+        stencil_pass.draw(0..3, lines);
 
         drop(stencil_pass);
         
+        let target = target.into();
         let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
             label: Some("Text Render Pass"),
             color_attachments: &[
-                Some(wgpu::RenderPassColorAttachment {
-                    view: &view,
-                    resolve_target: None,
-                    ops: wgpu::Operations {
-                        load: loadop,
-                        store: true,
-                    }
-                })
+                Some(target.color_attachment(loadop))
             ],
             depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
                 view: &stencil_texture_view,
@@ -229,8 +227,10 @@ impl TextRenderer {
                 })
             }),
         });
-        render_pass.set_pipeline(&self.render_pipeline);
-        render_pass.set_stencil_reference(0xff);
-        render_pass.draw(0..3, 0..1);
+        if target.apply_scissor(&mut render_pass) {
+            render_pass.set_pipeline(&self.render_pipeline);
+            render_pass.set_stencil_reference(0xff);
+            render_pass.draw(0..3, 0..1);
+        }
     }
 }
\ No newline at end of file
@@ -22,5 +22,5 @@ fn vert_main(
 
 @fragment
 fn frag_main() -> @location(0) vec4<f32> {
-    return vec4<f32>(0.0, 0.0, 0.0, 1.0);
+    return output_color(vec4<f32>(0.0, 0.0, 0.0, 1.0));
 }
\ No newline at end of file
@@ -0,0 +1,13 @@
+use bui::{
+    Error,
+    color::Color,
+};
+
+#[test]
+fn from_hex() {
+    assert_eq!(Color::from_hex("#f80").unwrap(), Color::from_rgb8(255, 136, 0));
+    assert_eq!(Color::from_hex("ff880080").unwrap(), Color::from_rgba8(255, 136, 0, 128));
+    for invalid in ["", "#", "#ff000", "#ggg", "#+f0000", "#-f0000", "#ff 000"] {
+        assert!(matches!(Color::from_hex(invalid), Err(Error::InvalidColor(_))), "{:?} parsed", invalid);
+    }
+}
@@ -0,0 +1,1003 @@
+use std::sync::Arc;
+use futures::executor::block_on;
+use bui::{
+    Error,
+    atlas::TextureAtlas,
+    blend::BlendMode,
+    blockfont,
+    clip::ClipStack,
+    color::Color,
+    fill::{ColorStop, Gradient},
+    ellipse::{EllipseBuffer, EllipseDescriptor, EllipseRenderer},
+    freeform_2dcapsule::{Freeform2DCapsule, Freeform2DCapsuleRenderer},
+    golden::{self, Golden},
+    image::{ImageDescriptor, ImageFit, ImageRaw, ImageRenderer, ImageTexture, UvRect},
+    line::{LineRaw, LineRenderer},
+    mask::{MaskRenderer, MaskShape, MaskShapeRaw},
+    nine_patch::{NinePatchDescriptor, NinePatchInsets, NinePatchRaw, NinePatchRenderer},
+    path::{Path, PathStroke},
+    polyline::{LineCap, LineJoin, LineStyle, Polyline, PolylineRenderer},
+    readback::RgbaImage,
+    rect::{CornerRadii, Points, RectDescriptor, RectRaw, RectRenderer, SizeAndCenter},
+    renderer::{Renderer, RendererDescriptor},
+    resolution_buffer::ResolutionBuffer,
+    scene::{Primitive, Scene, SceneRenderer},
+    shadow::{ShadowDescriptor, ShadowRenderer},
+    stroke::{Stroke, StrokeAlignment},
+    target::RenderTarget,
+    text::TextRenderer,
+};
+
+const WIDTH: u32 = 64;
+const HEIGHT: u32 = 64;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+
+// Machines without a software adapter cannot render the scenes at all, so they skip instead of failing.
+fn renderer() -> Option<Renderer> {
+    renderer_with_format(FORMAT)
+}
+
+fn renderer_with_format(format: wgpu::TextureFormat) -> Option<Renderer> {
+    match block_on(Renderer::new_headless_software(WIDTH, HEIGHT, format)) {
+        Ok(renderer) => Some(renderer),
+        Err(Error::NoAdapter) => {
+            eprintln!("No software adapter available, skipping golden test");
+            None
+        },
+        Err(e) => panic!("{}", e),
+    }
+}
+
+fn resolution_buffer(renderer: &Renderer) -> ResolutionBuffer {
+    let resolution_buffer = ResolutionBuffer::new(renderer.device());
+    resolution_buffer.set(&[WIDTH as f32, HEIGHT as f32], renderer.queue());
+    resolution_buffer
+}
+
+fn check<F>(name: &str, renderer: &Renderer, draw: F)
+where
+    F: FnOnce(&mut wgpu::CommandEncoder, RenderTarget),
+{
+    let image = block_on(golden::render(renderer, wgpu::Color::BLACK, draw)).unwrap();
+    check_image(name, &image);
+}
+
+fn check_image(name: &str, image: &RgbaImage) {
+    let golden = Golden::new(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden"),
+        concat!(env!("CARGO_TARGET_TMPDIR"), "/golden"),
+    );
+    if let Err(e) = golden.check(name, image) {
+        panic!("{}", e);
+    }
+}
+
+#[test]
+fn rect() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+        RectDescriptor {
+            sizing: Points { p1x: -0.75, p1y: 0.75, p2x: 0.25, p2y: -0.25 }.into(),
+            fill: Color::new(1.0, 0.0, 0.0, 1.0).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into(),
+        RectDescriptor {
+            sizing: Points { p1x: -0.25, p1y: 0.25, p2x: 0.75, p2y: -0.75 }.into(),
+            fill: Color::new(0.0, 0.0, 1.0, 1.0).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into(),
+    ]);
+
+    check("rect", &renderer, |encoder, target| {
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+// The same sRGB colour must produce the same bytes whether or not the target encodes to sRGB on write.
+#[test]
+fn rect_srgb_and_linear_targets() {
+    for format in [wgpu::TextureFormat::Rgba8Unorm, wgpu::TextureFormat::Rgba8UnormSrgb] {
+        let Some(renderer) = renderer_with_format(format) else { return };
+        let resolution_buffer = resolution_buffer(&renderer);
+        let mut rect_renderer = RectRenderer::new(renderer.device(), format, 1, BlendMode::Alpha, &resolution_buffer);
+        rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+            RectDescriptor {
+                sizing: SizeAndCenter { sx: 0.5, sy: 0.5, cx: 0.0, cy: 0.0 },
+                fill: Color::new(0.5, 0.25, 0.75, 1.0).into(),
+                corner_radii: CornerRadii::ZERO,
+                stroke: Stroke::NONE,
+            }.into(),
+        ]);
+
+        check("rect_color_space", &renderer, |encoder, target| {
+            rect_renderer.render_all(encoder, target, wgpu::LoadOp::Clear(Color::new(0.2, 0.2, 0.2, 1.0).to_wgpu(format)));
+        });
+
+        // A translucent colour given premultiplied must come out the same as given straight, on either target.
+        let color = Color::new(0.8, 0.4, 0.1, 0.5);
+        let translucent = |cx, fill: Color| -> RectRaw {
+            RectDescriptor {
+                sizing: SizeAndCenter { sx: 0.5, sy: 1.0, cx, cy: 0.0 },
+                fill: fill.into(),
+                corner_radii: CornerRadii::ZERO,
+                stroke: Stroke::NONE,
+            }.into()
+        };
+        rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[translucent(-0.5, color)]);
+        let mut premultiplied_renderer = RectRenderer::new(renderer.device(), format, 1, BlendMode::PremultipliedAlpha, &resolution_buffer);
+        premultiplied_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+            translucent(0.5, Color::new(color.r*color.a, color.g*color.a, color.b*color.a, color.a)),
+        ]);
+        let image = block_on(golden::render(&renderer, Color::new(0.2, 0.2, 0.2, 1.0).to_wgpu(format), |encoder, target| {
+            rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+            premultiplied_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+        })).unwrap();
+        let (straight, premultiplied) = (image.pixel(WIDTH/4, HEIGHT/2), image.pixel(WIDTH*3/4, HEIGHT/2));
+        assert!(straight.iter().zip(premultiplied).all(|(s, p)| s.abs_diff(p) <= 1), "{:?} != {:?} on {:?}", straight, premultiplied, format);
+    }
+}
+
+#[test]
+fn rect_blend_modes() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let modes = [BlendMode::Replace, BlendMode::Alpha, BlendMode::PremultipliedAlpha, BlendMode::Additive, BlendMode::Multiply];
+    let rect_renderers: Vec<RectRenderer> = modes.iter().enumerate().map(|(i, mode)| {
+        let color = Color::new(1.0, 0.5, 0.0, 0.5);
+        let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, *mode, &resolution_buffer);
+        rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+            RectDescriptor {
+                sizing: SizeAndCenter { sx: 0.2, sy: 0.8, cx: -0.8+0.4*i as f32, cy: 0.0 },
+                fill: (if *mode == BlendMode::PremultipliedAlpha { color.premultiplied() } else { color }).into(),
+                corner_radii: CornerRadii::ZERO,
+                stroke: Stroke::NONE,
+            }.into(),
+        ]);
+        rect_renderer
+    }).collect();
+
+    check("rect_blend_modes", &renderer, |encoder, target| {
+        let mut loadop = wgpu::LoadOp::Clear(wgpu::Color { r: 0.2, g: 0.4, b: 0.6, a: 1.0 });
+        for rect_renderer in &rect_renderers {
+            rect_renderer.render_all(encoder, target, loadop);
+            loadop = wgpu::LoadOp::Load;
+        }
+    });
+}
+
+// Far more rects than the buffer starts out with, set after a smaller batch so the buffer has to grow in place.
+#[test]
+fn rect_buffer_growth() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+        RectDescriptor {
+            sizing: SizeAndCenter::FULL,
+            fill: Color::WHITE.into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into(),
+    ]);
+    let checkerboard: Vec<RectRaw> = (0..64).filter(|i| (i/8+i%8)%2 == 0).map(|i| RectDescriptor {
+        sizing: SizeAndCenter { sx: 0.125, sy: 0.125, cx: -0.875+0.25*(i%8) as f32, cy: 0.875-0.25*(i/8) as f32 },
+        fill: Color::WHITE.into(),
+        corner_radii: CornerRadii::ZERO,
+        stroke: Stroke::NONE,
+    }.into()).collect();
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &checkerboard);
+
+    check("rect_buffer_growth", &renderer, |encoder, target| {
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+// Enough removals to trigger compaction, which must keep the surviving handles pointing at the right rects.
+#[test]
+fn rect_handles() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    let cell = |i: u32, color: Color| -> RectRaw {
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.1, sy: 0.1, cx: -0.75+0.25*(i%8) as f32, cy: 0.75-0.25*(i/8) as f32 },
+            fill: color.into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into()
+    };
+
+    let rects = rect_renderer.rect_buffer_mut();
+    let handles: Vec<_> = (0..24).map(|i| rects.insert(cell(i, Color::new(1.0, 0.0, 0.0, 1.0)))).collect();
+    rects.flush(renderer.device(), renderer.queue());
+
+    for handle in handles.iter().skip(1).step_by(3).chain(handles.iter().skip(2).step_by(3)) {
+        assert!(rects.remove(*handle).is_some());
+    }
+    assert!(rects.remove(handles[1]).is_none());
+    assert!(!rects.update(handles[2], cell(2, Color::WHITE)));
+    assert!(rects.update(handles[9], cell(9, Color::new(0.0, 1.0, 0.0, 1.0))));
+    rects.insert(cell(33, Color::new(0.0, 0.0, 1.0, 1.0)));
+    rects.flush(renderer.device(), renderer.queue());
+    assert_eq!(rects.len(), 9);
+
+    check("rect_handles", &renderer, |encoder, target| {
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+#[test]
+fn rounded_rect() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+        // The bottom left radius is larger than the rect is tall, so it gets clamped.
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.9, sy: 0.35, cx: 0.0, cy: 0.5 },
+            fill: Color::new(1.0, 0.0, 0.0, 1.0).into(),
+            corner_radii: CornerRadii {
+                top_left: 0.0,
+                top_right: 4.0,
+                bottom_right: 8.0,
+                bottom_left: 16.0,
+            },
+            stroke: Stroke::NONE,
+        }.into(),
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.8, sy: 0.2, cx: 0.0, cy: -0.5 },
+            fill: Color::new(0.0, 0.0, 1.0, 1.0).into(),
+            corner_radii: CornerRadii::all(100.0),
+            stroke: Stroke::NONE,
+        }.into(),
+    ]);
+
+    check("rounded_rect", &renderer, |encoder, target| {
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+#[test]
+fn ellipse() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut ellipse_renderer = EllipseRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    ellipse_renderer.set_ellipse_buffer(renderer.device(), renderer.queue(), &[
+        EllipseBuffer::from(EllipseDescriptor {
+            sizing: SizeAndCenter { sx: 0.8, sy: 0.4, cx: 0.0, cy: 0.4 },
+            fill: Color::new(0.0, 1.0, 0.0, 1.0).into(),
+            stroke: Stroke::NONE,
+        }),
+        EllipseBuffer::from(EllipseDescriptor {
+            sizing: SizeAndCenter { sx: 0.4, sy: 0.4, cx: 0.0, cy: -0.5 },
+            fill: Color::new(1.0, 1.0, 0.0, 1.0).into(),
+            stroke: Stroke::NONE,
+        }),
+    ]);
+
+    check("ellipse", &renderer, |encoder, target| {
+        ellipse_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+// Translucent fills must not show through their strokes, which is what stacking inset rects got wrong.
+#[test]
+fn stroke() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    let mut ellipse_renderer = EllipseRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.35, sy: 0.35, cx: -0.5, cy: 0.5 },
+            fill: Color::new(1.0, 0.0, 0.0, 0.5).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::new(4.0, Color::WHITE, StrokeAlignment::Inside),
+        }.into(),
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.3, sy: 0.3, cx: 0.5, cy: 0.5 },
+            fill: Color::new(0.0, 0.0, 1.0, 1.0).into(),
+            corner_radii: CornerRadii::all(6.0),
+            stroke: Stroke::new(4.0, Color::new(1.0, 1.0, 0.0, 1.0), StrokeAlignment::Center),
+        }.into(),
+    ]);
+    ellipse_renderer.set_ellipse_buffer(renderer.device(), renderer.queue(), &[
+        EllipseBuffer::from(EllipseDescriptor {
+            sizing: SizeAndCenter { sx: 0.3, sy: 0.2, cx: -0.5, cy: -0.5 },
+            fill: Color::new(0.0, 1.0, 0.0, 1.0).into(),
+            stroke: Stroke::new(3.0, Color::new(1.0, 0.0, 1.0, 1.0), StrokeAlignment::Outside),
+        }),
+        EllipseBuffer::from(EllipseDescriptor {
+            sizing: SizeAndCenter { sx: 0.35, sy: 0.35, cx: 0.5, cy: -0.5 },
+            fill: Color::TRANSPARENT.into(),
+            stroke: Stroke::new(2.0, Color::WHITE, StrokeAlignment::Center),
+        }),
+    ]);
+
+    check("stroke", &renderer, |encoder, target| {
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+        ellipse_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+// Gradient and flat instances share one buffer and draw.
+#[test]
+fn gradient() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    let mut ellipse_renderer = EllipseRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    let red = Color::new(1.0, 0.0, 0.0, 1.0);
+    let blue = Color::new(0.0, 0.0, 1.0, 1.0);
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.9, sy: 0.2, cx: 0.0, cy: 0.7 },
+            fill: Gradient::linear(0.0, &[ColorStop::new(0.0, red), ColorStop::new(1.0, blue)]).unwrap().into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into(),
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.4, sy: 0.4, cx: -0.5, cy: 0.0 },
+            fill: Color::WHITE.into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into(),
+        // Bottom to top, with a hard step from green to yellow half way.
+        RectDescriptor {
+            sizing: SizeAndCenter { sx: 0.4, sy: 0.6, cx: -0.5, cy: -0.3 },
+            fill: Gradient::linear(std::f32::consts::FRAC_PI_2, &[
+                ColorStop::new(0.0, Color::BLACK),
+                ColorStop::new(0.5, Color::new(0.0, 1.0, 0.0, 1.0)),
+                ColorStop::new(0.5, Color::new(1.0, 1.0, 0.0, 1.0)),
+                ColorStop::new(1.0, Color::WHITE),
+            ]).unwrap().into(),
+            corner_radii: CornerRadii::all(6.0),
+            stroke: Stroke::new(1.0, Color::WHITE, StrokeAlignment::Inside),
+        }.into(),
+    ]);
+    ellipse_renderer.set_ellipse_buffer(renderer.device(), renderer.queue(), &[
+        EllipseBuffer::from(EllipseDescriptor {
+            sizing: SizeAndCenter { sx: 0.4, sy: 0.4, cx: 0.5, cy: -0.3 },
+            fill: Gradient::radial([0.3, 0.3], 1.0, &[ColorStop::new(0.0, Color::WHITE), ColorStop::new(1.0, blue.with_alpha(0.0))]).unwrap().into(),
+            stroke: Stroke::NONE,
+        }),
+    ]);
+
+    check("gradient", &renderer, |encoder, target| {
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+        ellipse_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+// Cards on a light background: a soft offset shadow, a hard spread-out one and a blurred one inset by negative spread.
+#[test]
+fn shadow() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut shadow_renderer = ShadowRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    // The second card is square, and stays square under its spread.
+    let cards = [
+        (SizeAndCenter { sx: 0.35, sy: 0.35, cx: -0.45, cy: 0.45 }, [2.0, 3.0], 8.0, 0.0, CornerRadii::all(4.0)),
+        (SizeAndCenter { sx: 0.35, sy: 0.35, cx: 0.45, cy: 0.45 }, [0.0, 0.0], 0.0, 3.0, CornerRadii::ZERO),
+        (SizeAndCenter { sx: 0.8, sy: 0.25, cx: 0.0, cy: -0.5 }, [0.0, 4.0], 12.0, -2.0, CornerRadii::all(4.0)),
+    ];
+    let shadows: Vec<_> = cards.iter().map(|(sizing, offset, blur_radius, spread, corner_radii)| ShadowDescriptor {
+        sizing: *sizing,
+        offset: *offset,
+        blur_radius: *blur_radius,
+        spread: *spread,
+        color: Color::new(0.0, 0.0, 0.0, 0.6),
+        corner_radii: *corner_radii,
+    }.into()).collect();
+    shadow_renderer.set_shadow_buffer(renderer.device(), renderer.queue(), &shadows);
+    let rects: Vec<_> = cards.iter().map(|(sizing, .., corner_radii)| RectDescriptor {
+        sizing: *sizing,
+        fill: Color::WHITE.into(),
+        corner_radii: *corner_radii,
+        stroke: Stroke::NONE,
+    }.into()).collect();
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &rects);
+
+    check("shadow", &renderer, |encoder, target| {
+        shadow_renderer.render_all(encoder, target, wgpu::LoadOp::Clear(wgpu::Color { r: 0.8, g: 0.8, b: 0.8, a: 1.0 }));
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+// A 4x2 texture drawn stretched with both filters, letterboxed, and cropped from a tinted sub-rect.
+#[test]
+fn image() {
+    let Some(renderer) = renderer() else { return };
+    let mut nearest_renderer = ImageRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha);
+    let mut linear_renderer = ImageRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha);
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]];
+    let image = RgbaImage {
+        width: 4,
+        height: 2,
+        data: (0..8).flat_map(|i| colors[(i%4+i/4)%4]).collect(),
+    };
+    let nearest = ImageTexture::from_image(renderer.device(), renderer.queue(), &image, wgpu::FilterMode::Nearest);
+    let linear = ImageTexture::from_image(renderer.device(), renderer.queue(), &image, wgpu::FilterMode::Linear);
+
+    let descriptor = |sizing: SizeAndCenter, uv: UvRect, fit: ImageFit, tint: Color| -> ImageRaw {
+        ImageDescriptor {
+            sizing,
+            uv,
+            texture_size: nearest.size(),
+            fit,
+            tint,
+            resx: WIDTH as f32,
+            resy: HEIGHT as f32,
+        }.into()
+    };
+    nearest_renderer.set_image_buffer(renderer.device(), renderer.queue(), &[
+        descriptor(SizeAndCenter { sx: 0.45, sy: 0.45, cx: -0.5, cy: 0.5 }, UvRect::FULL, ImageFit::Stretch, Color::WHITE),
+        descriptor(SizeAndCenter { sx: 0.45, sy: 0.45, cx: -0.5, cy: -0.5 }, UvRect::FULL, ImageFit::Contain, Color::WHITE),
+        descriptor(SizeAndCenter { sx: 0.45, sy: 0.45, cx: 0.5, cy: -0.5 }, UvRect::from_pixels(1, 0, 3, 2, nearest.size()), ImageFit::Cover, Color::new(1.0, 1.0, 1.0, 0.5)),
+    ]);
+    linear_renderer.set_image_buffer(renderer.device(), renderer.queue(), &[
+        descriptor(SizeAndCenter { sx: 0.45, sy: 0.45, cx: 0.5, cy: 0.5 }, UvRect::FULL, ImageFit::Stretch, Color::WHITE),
+    ]);
+
+    check("image", &renderer, |encoder, target| {
+        nearest_renderer.render_all(encoder, target, wgpu::LoadOp::Load, &nearest);
+        linear_renderer.render_all(encoder, target, wgpu::LoadOp::Load, &linear);
+    });
+}
+
+// Twelve 8x8 icons fill one 32x32 page (nine fit with padding) and spill onto a second, drawn with one call per page.
+#[test]
+fn atlas() {
+    let Some(renderer) = renderer() else { return };
+    let mut image_renderer = ImageRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha);
+    let mut atlas = TextureAtlas::new(32, wgpu::FilterMode::Nearest);
+    let regions: Vec<_> = (0..12).map(|i| {
+        let color = Color::from_hsv(i as f32*30.0, 1.0, 1.0, 1.0).to_rgba8();
+        let icon = RgbaImage {
+            width: 8,
+            height: 8,
+            // A dark border shows that neighbouring icons do not bleed into each other.
+            data: (0..64).flat_map(|p| if p%8 == 0 || p/8 == 0 { [0, 0, 0, 255] } else { color }).collect(),
+        };
+        atlas.insert(renderer.device(), renderer.queue(), &icon).unwrap()
+    }).collect();
+    assert_eq!(atlas.page_count(), 2);
+    assert!(regions[..9].iter().all(|region| region.page == 0));
+    assert!(regions[9..].iter().all(|region| region.page == 1));
+    let too_large = RgbaImage { width: 32, height: 1, data: vec![0; 128] };
+    assert!(atlas.insert(renderer.device(), renderer.queue(), &too_large).is_none());
+
+    let instances: Vec<ImageRaw> = regions.iter().enumerate().map(|(i, region)| ImageDescriptor {
+        sizing: SizeAndCenter { sx: 0.2, sy: 0.2, cx: -0.75+0.5*(i%4) as f32, cy: 0.6-0.6*(i/4) as f32 },
+        uv: region.uv,
+        texture_size: [atlas.page_size(); 2],
+        fit: ImageFit::Stretch,
+        tint: Color::WHITE,
+        resx: WIDTH as f32,
+        resy: HEIGHT as f32,
+    }.into()).collect();
+    image_renderer.set_image_buffer(renderer.device(), renderer.queue(), &instances);
+
+    check("atlas", &renderer, |encoder, target| {
+        image_renderer.render_range(encoder, target, wgpu::LoadOp::Load, atlas.page(0), 0..9);
+        image_renderer.render_range(encoder, target, wgpu::LoadOp::Load, atlas.page(1), 9..12);
+    });
+
+    // Linear filtering at a packed image's edges samples only its own edge texels, not its neighbours or empty space.
+    let mut linear_atlas = TextureAtlas::new(32, wgpu::FilterMode::Linear);
+    let solid = |color: [u8; 4]| RgbaImage { width: 4, height: 4, data: color.repeat(16) };
+    let green = [0, 255, 0, 255];
+    let region = linear_atlas.insert(renderer.device(), renderer.queue(), &solid(green)).unwrap();
+    linear_atlas.insert(renderer.device(), renderer.queue(), &solid([255, 0, 255, 255])).unwrap();
+    image_renderer.set_image_buffer(renderer.device(), renderer.queue(), &[ImageDescriptor {
+        sizing: SizeAndCenter { sx: 0.5, sy: 0.5, cx: 0.0, cy: 0.0 },
+        uv: region.uv,
+        texture_size: [linear_atlas.page_size(); 2],
+        fit: ImageFit::Stretch,
+        tint: Color::WHITE,
+        resx: WIDTH as f32,
+        resy: HEIGHT as f32,
+    }.into()]);
+    let image = block_on(golden::render(&renderer, wgpu::Color::BLACK, |encoder, target| {
+        image_renderer.render_all(encoder, target, wgpu::LoadOp::Load, linear_atlas.page(0));
+    })).unwrap();
+    for (x, y) in [(16, 32), (47, 32), (32, 16), (32, 47), (47, 47)] {
+        assert_eq!(image.pixel(x, y), green, "fringe at ({}, {})", x, y);
+    }
+    check_image("atlas_linear", &image);
+}
+
+// A 9x9 frame with 3px borders stretched wide, tall and below the size of its borders.
+#[test]
+fn nine_patch() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut nine_patch_renderer = NinePatchRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    let frame = RgbaImage {
+        width: 9,
+        height: 9,
+        data: (0..81).flat_map(|i| {
+            let (x, y) = (i%9, i/9);
+            let border = |c: u32| !(3..6).contains(&c);
+            match (border(x), border(y)) {
+                (true, true) => [255, 0, 0, 255],
+                (true, false) | (false, true) => [255, 255, 255, 255],
+                (false, false) => [0, 0, 255, 255],
+            }
+        }).collect(),
+    };
+    let texture = ImageTexture::from_image(renderer.device(), renderer.queue(), &frame, wgpu::FilterMode::Nearest);
+    let descriptor = |sizing: SizeAndCenter| -> NinePatchRaw {
+        NinePatchDescriptor {
+            sizing,
+            uv: UvRect::FULL,
+            texture_size: texture.size(),
+            insets: NinePatchInsets::all(3.0),
+            tint: Color::WHITE,
+        }.into()
+    };
+    nine_patch_renderer.set_nine_patch_buffer(renderer.device(), renderer.queue(), &[
+        descriptor(SizeAndCenter { sx: 0.85, sy: 0.3, cx: 0.0, cy: 0.55 }),
+        descriptor(SizeAndCenter { sx: 0.3, sy: 0.5, cx: -0.55, cy: -0.4 }),
+        descriptor(SizeAndCenter { sx: 0.0625, sy: 0.0625, cx: 0.5, cy: -0.4 }),
+    ]);
+
+    check("nine_patch", &renderer, |encoder, target| {
+        nine_patch_renderer.render_all(encoder, target, wgpu::LoadOp::Load, &texture);
+    });
+}
+
+#[cfg(feature = "decode")]
+#[test]
+fn image_decode() {
+    let image = RgbaImage {
+        width: 2,
+        height: 1,
+        data: vec![255, 0, 0, 255, 0, 0, 255, 128],
+    };
+    let mut png = Vec::new();
+    image.write_png(&mut png).unwrap();
+    assert_eq!(bui::image::decode(&png).unwrap(), image);
+    assert!(matches!(bui::image::decode(b"not an image"), Err(Error::ImageDecode(_))));
+}
+
+#[test]
+fn line() {
+    let Some(renderer) = renderer() else { return };
+    let mut line_renderer = LineRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha);
+    line_renderer.set_line_buffer(renderer.device(), renderer.queue(), &[
+        LineRaw { p1: [-0.9, -0.9], p2: [0.9, 0.9] },
+        LineRaw { p1: [-0.9, 0.5], p2: [0.9, 0.5] },
+    ]);
+
+    check("line", &renderer, |encoder, target| {
+        line_renderer.render_all(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE));
+    });
+}
+
+// Caps on the top three lines, then miter, round and bevel joins. The translucent triangle should be evenly shaded,
+// without darker corners where its segments meet.
+#[test]
+fn polyline() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut polyline_renderer = PolylineRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+
+    let black = LineStyle::new(6.0, Color::new(0.0, 0.0, 0.0, 1.0));
+    let blue = LineStyle::new(6.0, Color::new(0.0, 0.0, 1.0, 1.0));
+    let mut polylines = Vec::new();
+    for (y, cap) in [(0.8, LineCap::Butt), (0.6, LineCap::Round), (0.4, LineCap::Square)] {
+        polylines.push(Polyline::line([-0.7, y], [0.7, y], LineStyle { cap, ..black }));
+    }
+    for (x, join) in [(-0.6, LineJoin::Miter), (0.0, LineJoin::Round), (0.6, LineJoin::Bevel)] {
+        polylines.push(Polyline {
+            points: vec![[x-0.25, 0.1], [x, -0.3], [x+0.25, 0.1]],
+            closed: false,
+            style: LineStyle { join, ..blue },
+        });
+    }
+    polylines.push(Polyline {
+        points: vec![[-0.6, -0.5], [0.6, -0.5], [0.0, -0.9]],
+        closed: true,
+        style: LineStyle {
+            join: LineJoin::Round,
+            ..LineStyle::new(4.0, Color::new(1.0, 0.0, 0.0, 0.5))
+        },
+    });
+    let segments: Vec<_> = polylines.iter().flat_map(Polyline::to_raw).collect();
+    assert_eq!(segments.len(), 3+3*2+3);
+    polyline_renderer.set_segment_buffer(renderer.device(), renderer.queue(), &segments);
+
+    check("polyline", &renderer, |encoder, target| {
+        polyline_renderer.render_all(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE));
+    });
+}
+
+// A solid closed outline with curved corners, a dashed S curve with round caps, and a dashed square whose dashes run
+// on over the point it was started from.
+#[test]
+fn path() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut polyline_renderer = PolylineRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+
+    let mut outline = Path::new();
+    outline.move_to(-0.6, 0.9)
+        .line_to(0.6, 0.9)
+        .quad_to(0.9, 0.9, 0.9, 0.6)
+        .line_to(0.9, 0.3)
+        .line_to(-0.9, 0.3)
+        .line_to(-0.9, 0.6)
+        .quad_to(-0.9, 0.9, -0.6, 0.9)
+        .close()
+        .line_to(-0.6, 0.5);
+    let subpaths = outline.flatten(4);
+    assert_eq!(subpaths.len(), 2);
+    assert!(subpaths[0].closed);
+    assert_eq!(subpaths[0].points.len(), 2+4+3+4);
+    assert_eq!(subpaths[1].points, vec![[-0.6, 0.9], [-0.6, 0.5]]);
+    // Curves still reach their end points when asked for no segments.
+    assert_eq!(outline.flatten(0)[0].points[2], [0.9, 0.6]);
+
+    let mut curve = Path::new();
+    curve.move_to(-0.9, -0.1).cubic_to(-0.2, 0.4, 0.2, -0.6, 0.9, -0.1);
+
+    let mut square = Path::new();
+    square.move_to(-0.7, -0.4).line_to(0.7, -0.4).line_to(0.7, -0.9).line_to(-0.7, -0.9).close();
+
+    let black = LineStyle::new(3.0, Color::new(0.0, 0.0, 0.0, 1.0));
+    let mut segments = outline.stroke(&PathStroke::solid(LineStyle {
+        join: LineJoin::Round,
+        ..LineStyle::new(3.0, Color::new(0.0, 0.0, 1.0, 1.0))
+    }), 4, WIDTH as f32, HEIGHT as f32);
+    segments.extend(curve.stroke(&PathStroke::dashed(LineStyle { cap: LineCap::Round, ..black }, vec![6.0, 5.0]), 16, WIDTH as f32, HEIGHT as f32));
+    segments.extend(square.stroke(&PathStroke {
+        dash_offset: 3.0,
+        ..PathStroke::dashed(LineStyle::new(2.0, Color::new(1.0, 0.0, 0.0, 1.0)), vec![7.0])
+    }, 4, WIDTH as f32, HEIGHT as f32));
+    polyline_renderer.set_segment_buffer(renderer.device(), renderer.queue(), &segments);
+
+    check("path", &renderer, |encoder, target| {
+        polyline_renderer.render_all(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE));
+    });
+}
+
+#[test]
+fn freeform_2dcapsule() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut capsule_renderer = Freeform2DCapsuleRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    capsule_renderer.set_capsule_buffer(renderer.device(), renderer.queue(), &[
+        Freeform2DCapsule {
+            p1: [-0.6, -0.4],
+            p2: [0.6, 0.4],
+            radius_px: 4.8,
+            color: Color::named("cyan").unwrap(),
+        },
+    ]);
+
+    check("freeform_2dcapsule", &renderer, |encoder, target| {
+        capsule_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+#[test]
+fn text() {
+    let Some(renderer) = renderer() else { return };
+    let mut text_renderer = TextRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, WIDTH, HEIGHT);
+    // A square outline with a square hole, wound like a glyph contour.
+    let contour = |half: f32| [
+        LineRaw { p1: [-half, half], p2: [half, half] },
+        LineRaw { p1: [half, half], p2: [half, -half] },
+        LineRaw { p1: [half, -half], p2: [-half, -half] },
+        LineRaw { p1: [-half, -half], p2: [-half, half] },
+    ];
+    let mut lines = contour(0.8).to_vec();
+    lines.extend_from_slice(&contour(0.4));
+    text_renderer.set_line_buffer(renderer.device(), renderer.queue(), &lines);
+
+    check("text", &renderer, |encoder, target| {
+        text_renderer.render_all(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE));
+    });
+}
+
+#[test]
+fn blockfont_fill_text() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let rects: Vec<RectRaw> = blockfont::FillText {
+        text: "BUI 42",
+        placement_area: SizeAndCenter::FULL,
+        resx: WIDTH as f32,
+        resy: HEIGHT as f32,
+        color: Color::WHITE,
+    }.into();
+    let mut rect_renderer = RectRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &rects);
+
+    check("blockfont_fill_text", &renderer, |encoder, target| {
+        rect_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}
+
+#[test]
+fn rect_on_external_device() {
+    let instance = wgpu::Instance::new(wgpu::Backends::all());
+    let Some(adapter) = block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+        power_preference: wgpu::PowerPreference::default(),
+        force_fallback_adapter: true,
+        compatible_surface: None,
+    })) else { return };
+    let (device, queue) = block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None)).unwrap();
+    let renderer = Renderer::from_device(Arc::new(device), Arc::new(queue), FORMAT, WIDTH, HEIGHT);
+    let resolution_buffer = resolution_buffer(&renderer);
+
+    // The "application" owns the texture and the encoder; bui only records into them.
+    let texture = renderer.device().create_texture(&wgpu::TextureDescriptor {
+        label: Some("Application Frame"),
+        size: wgpu::Extent3d {
+            width: WIDTH,
+            height: HEIGHT,
+            ..Default::default()
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: FORMAT,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    });
+    let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+    let mut rect_renderer = RectRenderer::new(renderer.device(), renderer.config().format, 1, BlendMode::Alpha, &resolution_buffer);
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+        RectDescriptor {
+            sizing: Points { p1x: -0.75, p1y: 0.75, p2x: 0.25, p2y: -0.25 }.into(),
+            fill: Color::new(1.0, 0.0, 0.0, 1.0).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into(),
+        RectDescriptor {
+            sizing: Points { p1x: -0.25, p1y: 0.25, p2x: 0.75, p2y: -0.75 }.into(),
+            fill: Color::new(0.0, 0.0, 1.0, 1.0).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }.into(),
+    ]);
+
+    let mut encoder = renderer.device().create_command_encoder(&wgpu::CommandEncoderDescriptor {
+        label: Some("Application Encoder"),
+    });
+    rect_renderer.render_all(&mut encoder, &view, wgpu::LoadOp::Clear(wgpu::Color::BLACK));
+    renderer.queue().submit(std::iter::once(encoder.finish()));
+
+    let image = block_on(renderer.read_texture(&texture)).unwrap();
+    check_image("rect", &image);
+}
+
+// Pushed out of order on purpose: painting must follow z, and text must split the pass without losing what came before.
+#[test]
+fn scene() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+    let mut scene_renderer = SceneRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer, WIDTH, HEIGHT);
+
+    let mut scene = Scene::new();
+    scene.push(3, Freeform2DCapsule {
+        p1: [-0.8, -0.8],
+        p2: [0.8, 0.8],
+        radius_px: 3.2,
+        color: Color::named("orange").unwrap(),
+    });
+    scene.push(1, RectRaw::from(RectDescriptor {
+        sizing: SizeAndCenter { sx: 1.0, sy: 1.0, cx: -0.4, cy: 0.4 },
+        fill: Color::new(1.0, 0.0, 0.0, 1.0).into(),
+        corner_radii: CornerRadii::ZERO,
+        stroke: Stroke::NONE,
+    }));
+    scene.push(0, EllipseBuffer::from(EllipseDescriptor {
+        sizing: SizeAndCenter { sx: 1.2, sy: 1.2, cx: 0.0, cy: 0.0 },
+        fill: Color::new(0.0, 1.0, 0.0, 1.0).into(),
+        stroke: Stroke::NONE,
+    }));
+    scene.push(1, RectRaw::from(RectDescriptor {
+        sizing: SizeAndCenter { sx: 1.0, sy: 1.0, cx: 0.4, cy: -0.4 },
+        fill: Color::new(0.0, 0.0, 1.0, 0.5).into(),
+        corner_radii: CornerRadii::ZERO,
+        stroke: Stroke::NONE,
+    }));
+    scene.push(2, Primitive::Text(vec![
+        LineRaw { p1: [-0.3, 0.9], p2: [0.3, 0.9] },
+        LineRaw { p1: [0.3, 0.9], p2: [0.3, 0.6] },
+        LineRaw { p1: [0.3, 0.6], p2: [-0.3, 0.6] },
+        LineRaw { p1: [-0.3, 0.6], p2: [-0.3, 0.9] },
+    ]));
+    scene_renderer.prepare(renderer.device(), renderer.queue(), &scene);
+
+    check("scene", &renderer, |encoder, target| {
+        scene_renderer.render(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE));
+    });
+}
+
+// Nested clips intersect, and the scene keeps the clip each primitive was pushed under even across text passes.
+#[test]
+fn clip() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+
+    let mut clips = ClipStack::new();
+    clips.push(Points { p1x: -1.0, p1y: 1.0, p2x: 0.0, p2y: -1.0 });
+    clips.push(SizeAndCenter { sx: 1.0, sy: 0.5, cx: 0.0, cy: 0.5 });
+    assert_eq!(clips.depth(), 2);
+    let scissor = clips.scissor_rect(WIDTH, HEIGHT);
+    assert_eq!(scissor.map(|scissor| (scissor.x, scissor.y, scissor.width, scissor.height)), Some((0, 0, 32, 32)));
+
+    let mut ellipse_renderer = EllipseRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    ellipse_renderer.set_ellipse_buffer(renderer.device(), renderer.queue(), &[
+        EllipseBuffer::from(EllipseDescriptor {
+            sizing: SizeAndCenter { sx: 1.0, sy: 1.0, cx: 0.0, cy: 0.0 },
+            fill: Color::new(1.0, 1.0, 0.0, 1.0).into(),
+            stroke: Stroke::NONE,
+        }),
+    ]);
+
+    let mut scene_renderer = SceneRenderer::new(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer, WIDTH, HEIGHT);
+    let mut scene = Scene::new();
+    scene.push_clip(Points { p1x: 0.0, p1y: 1.0, p2x: 1.0, p2y: -1.0 });
+    scene.push(0, RectRaw::from(RectDescriptor {
+        sizing: SizeAndCenter { sx: 1.0, sy: 1.0, cx: 0.0, cy: 0.0 },
+        fill: Color::new(0.0, 0.0, 1.0, 1.0).into(),
+        corner_radii: CornerRadii::ZERO,
+        stroke: Stroke::NONE,
+    }));
+    scene.push_clip(Points { p1x: -1.0, p1y: 0.0, p2x: 1.0, p2y: -1.0 });
+    scene.push(1, Primitive::Text(vec![
+        LineRaw { p1: [-0.9, -0.9], p2: [0.9, -0.9] },
+        LineRaw { p1: [0.9, -0.9], p2: [0.0, 0.9] },
+        LineRaw { p1: [0.0, 0.9], p2: [-0.9, -0.9] },
+    ]));
+    scene.pop_clip();
+    scene.push(2, RectRaw::from(RectDescriptor {
+        sizing: SizeAndCenter { sx: 0.25, sy: 0.25, cx: 0.0, cy: 0.5 },
+        fill: Color::new(1.0, 0.0, 0.0, 1.0).into(),
+        corner_radii: CornerRadii::ZERO,
+        stroke: Stroke::NONE,
+    }));
+    scene.pop_clip();
+    scene.push(2, RectRaw::from(RectDescriptor {
+        sizing: SizeAndCenter { sx: 0.25, sy: 0.25, cx: -0.5, cy: -0.5 },
+        fill: Color::new(0.0, 1.0, 0.0, 1.0).into(),
+        corner_radii: CornerRadii::ZERO,
+        stroke: Stroke::NONE,
+    }));
+    // Clips that do not overlap, or lie off the target, leave nothing to draw into.
+    scene.push_clip(Points { p1x: -1.0, p1y: 1.0, p2x: -0.5, p2y: 0.5 });
+    scene.push_clip(Points { p1x: 0.5, p1y: -0.5, p2x: 1.0, p2y: -1.0 });
+    scene.push(3, RectRaw::from(RectDescriptor {
+        sizing: SizeAndCenter { sx: 1.0, sy: 1.0, cx: 0.0, cy: 0.0 },
+        fill: Color::new(1.0, 0.0, 1.0, 1.0).into(),
+        corner_radii: CornerRadii::ZERO,
+        stroke: Stroke::NONE,
+    }));
+    scene.push(3, Primitive::Text(vec![
+        LineRaw { p1: [-0.9, -0.9], p2: [0.9, -0.9] },
+        LineRaw { p1: [0.9, -0.9], p2: [0.0, 0.9] },
+        LineRaw { p1: [0.0, 0.9], p2: [-0.9, -0.9] },
+    ]));
+    scene.pop_clip();
+    scene.pop_clip();
+    scene_renderer.prepare(renderer.device(), renderer.queue(), &scene);
+
+    let mut disjoint = ClipStack::new();
+    disjoint.push(Points { p1x: -1.0, p1y: 1.0, p2x: -0.5, p2y: 0.5 });
+    disjoint.push(Points { p1x: 0.5, p1y: -0.5, p2x: 1.0, p2y: -1.0 });
+    let empty = disjoint.scissor_rect(WIDTH, HEIGHT);
+    assert!(empty.unwrap().is_empty());
+    let mut off_screen = ClipStack::new();
+    off_screen.push(Points { p1x: 2.0, p1y: 3.0, p2x: 3.0, p2y: 2.0 });
+    assert!(off_screen.scissor_rect(WIDTH, HEIGHT).unwrap().is_empty());
+
+    check("clip", &renderer, |encoder, target| {
+        scene_renderer.render(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE));
+        ellipse_renderer.render_all(encoder, target.with_scissor(scissor), wgpu::LoadOp::Load);
+        ellipse_renderer.render_all(encoder, target.with_scissor(empty), wgpu::LoadOp::Load);
+    });
+}
+
+// The nested mask is the union of a rect and a glyph outline, limited to the circle pushed before it. Popping it leaves
+// the circle masking again.
+#[test]
+fn mask() {
+    let Some(renderer) = renderer() else { return };
+    let resolution_buffer = resolution_buffer(&renderer);
+
+    let mut mask_renderer = MaskRenderer::new(renderer.device(), 1, &resolution_buffer, WIDTH, HEIGHT);
+    mask_renderer.set_shape_buffer(renderer.device(), renderer.queue(), &[
+        MaskShapeRaw::from(MaskShape::Ellipse {
+            sizing: SizeAndCenter { sx: 0.8, sy: 0.8, cx: 0.0, cy: 0.0 },
+        }),
+        MaskShapeRaw::from(MaskShape::Rect {
+            sizing: SizeAndCenter { sx: 0.5, sy: 1.0, cx: -0.5, cy: 0.0 },
+            corner_radii: CornerRadii::all(8.0),
+        }),
+    ]);
+    mask_renderer.set_outline_buffer(renderer.device(), renderer.queue(), &[
+        LineRaw { p1: [0.1, -0.9], p2: [0.9, -0.9] },
+        LineRaw { p1: [0.9, -0.9], p2: [0.5, 0.5] },
+        LineRaw { p1: [0.5, 0.5], p2: [0.1, -0.9] },
+    ]);
+
+    let mut rect_renderer = RectRenderer::new_masked(renderer.device(), FORMAT, 1, BlendMode::Alpha, &resolution_buffer);
+    let full = SizeAndCenter { sx: 1.0, sy: 1.0, cx: 0.0, cy: 0.0 };
+    rect_renderer.set_rect_buffer(renderer.device(), renderer.queue(), &[
+        RectRaw::from(RectDescriptor {
+            sizing: full,
+            fill: Color::new(1.0, 0.0, 0.0, 1.0).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }),
+        RectRaw::from(RectDescriptor {
+            sizing: full,
+            fill: Color::new(0.0, 0.0, 1.0, 1.0).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }),
+        RectRaw::from(RectDescriptor {
+            sizing: SizeAndCenter { sx: 1.0, sy: 0.15, cx: 0.0, cy: 0.7 },
+            fill: Color::new(0.0, 1.0, 0.0, 1.0).into(),
+            corner_radii: CornerRadii::ZERO,
+            stroke: Stroke::NONE,
+        }),
+    ]);
+
+    check("mask", &renderer, |encoder, target| {
+        mask_renderer.push(encoder, 0..1, 0..0);
+        rect_renderer.draw(&mut mask_renderer.begin_masked_pass(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE)), 0..1);
+        mask_renderer.push(encoder, 1..2, 0..3);
+        assert_eq!(mask_renderer.depth(), 2);
+        rect_renderer.draw(&mut mask_renderer.begin_masked_pass(encoder, target, wgpu::LoadOp::Load), 1..2);
+        mask_renderer.pop(encoder);
+        rect_renderer.draw(&mut mask_renderer.begin_masked_pass(encoder, target, wgpu::LoadOp::Load), 2..3);
+        mask_renderer.pop(encoder);
+        assert_eq!(mask_renderer.depth(), 0);
+    });
+}
+
+// The diagonal edges should come out smoothed by the resolve even though neither renderer anti-aliases them itself.
+#[test]
+fn msaa() {
+    let descriptor = RendererDescriptor {
+        force_fallback_adapter: true,
+        sample_count: 4,
+        ..Default::default()
+    };
+    let mut renderer = match block_on(Renderer::new_headless_with_descriptor(WIDTH, HEIGHT, FORMAT, &descriptor)) {
+        Ok(renderer) => renderer,
+        Err(Error::NoAdapter) | Err(Error::UnsupportedSampleCount(_)) => {
+            eprintln!("No multisampling software adapter available, skipping golden test");
+            return;
+        },
+        Err(e) => panic!("{}", e),
+    };
+    assert!(matches!(renderer.set_sample_count(3), Err(Error::UnsupportedSampleCount(3))));
+    assert_eq!(renderer.sample_count(), 4);
+
+    let mut text_renderer = TextRenderer::new(renderer.device(), FORMAT, renderer.sample_count(), BlendMode::Alpha, WIDTH, HEIGHT);
+    text_renderer.set_line_buffer(renderer.device(), renderer.queue(), &[
+        LineRaw { p1: [-0.8, -0.8], p2: [0.8, -0.8] },
+        LineRaw { p1: [0.8, -0.8], p2: [0.0, 0.8] },
+        LineRaw { p1: [0.0, 0.8], p2: [-0.8, -0.8] },
+    ]);
+    let mut line_renderer = LineRenderer::new(renderer.device(), FORMAT, renderer.sample_count(), BlendMode::Alpha);
+    line_renderer.set_line_buffer(renderer.device(), renderer.queue(), &[
+        LineRaw { p1: [-0.9, 0.9], p2: [0.9, -0.3] },
+    ]);
+
+    check("msaa", &renderer, |encoder, target| {
+        text_renderer.render_all(encoder, target, wgpu::LoadOp::Clear(wgpu::Color::WHITE));
+        line_renderer.render_all(encoder, target, wgpu::LoadOp::Load);
+    });
+}